@@ -0,0 +1,12 @@
+ALTER TABLE redirects ADD COLUMN clicks INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS click_events (
+    id INTEGER NOT NULL PRIMARY KEY,
+    redirect_id TEXT NOT NULL,
+    clicked_at INTEGER NOT NULL,
+    referrer TEXT,
+    user_agent TEXT,
+    FOREIGN KEY(redirect_id) REFERENCES redirects(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_click_events_redirect_id_clicked_at ON click_events(redirect_id, clicked_at);
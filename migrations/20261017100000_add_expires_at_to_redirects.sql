@@ -0,0 +1,3 @@
+ALTER TABLE redirects ADD COLUMN expires_at INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_redirects_expires_at ON redirects(expires_at);
@@ -0,0 +1,3 @@
+ALTER TABLE redirects ADD COLUMN max_clicks INTEGER;
+
+ALTER TABLE redirects ADD COLUMN remaining_clicks INTEGER;
@@ -0,0 +1 @@
+ALTER TABLE redirects ADD COLUMN redirect_type INTEGER NOT NULL DEFAULT 307;
@@ -0,0 +1,11 @@
+ALTER TABLE redirects ADD COLUMN created_at INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE redirects ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE redirects ADD COLUMN updated_by TEXT;
+
+-- existing redirects have no known creation time, use the time of the migration instead
+UPDATE redirects SET
+    created_at = CAST(strftime('%s', 'now') AS INTEGER),
+    updated_at = CAST(strftime('%s', 'now') AS INTEGER),
+    updated_by = owner;
+
+CREATE INDEX IF NOT EXISTS idx_redirects_created_at ON redirects(created_at);
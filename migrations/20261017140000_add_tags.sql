@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS tags (
+    id INTEGER NOT NULL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE IF NOT EXISTS redirect_tags (
+    redirect_id TEXT NOT NULL,
+    tag_id INTEGER NOT NULL,
+    PRIMARY KEY(redirect_id, tag_id),
+    FOREIGN KEY(redirect_id) REFERENCES redirects(id) ON DELETE CASCADE,
+    FOREIGN KEY(tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_redirect_tags_tag_id ON redirect_tags(tag_id);
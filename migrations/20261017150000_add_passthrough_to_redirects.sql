@@ -0,0 +1 @@
+ALTER TABLE redirects ADD COLUMN passthrough BOOLEAN NOT NULL DEFAULT 0;
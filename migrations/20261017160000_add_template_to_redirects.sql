@@ -0,0 +1 @@
+ALTER TABLE redirects ADD COLUMN template BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+ALTER TABLE redirects ADD COLUMN query_params TEXT NOT NULL DEFAULT '{}';
@@ -0,0 +1,51 @@
+ALTER TABLE redirects ADD COLUMN alias_key TEXT;
+ALTER TABLE redirects ADD COLUMN case_insensitive BOOLEAN;
+
+-- aliases that only differ in case can't share a key, they are recorded and reported on startup
+CREATE TABLE alias_collisions (
+    redirect_id TEXT PRIMARY KEY NOT NULL REFERENCES redirects(id) ON DELETE CASCADE,
+    alias_key TEXT NOT NULL
+);
+
+INSERT INTO alias_collisions (redirect_id, alias_key)
+SELECT id, lower(alias) FROM redirects
+WHERE lower(alias) IN (
+    SELECT lower(alias) FROM redirects GROUP BY lower(alias) HAVING COUNT(*) > 1
+);
+
+-- the oldest of colliding redirects keeps the key, the others get a key no alias can take
+-- because ':' is not allowed in aliases
+UPDATE redirects SET alias_key = CASE
+    WHEN id = (
+        SELECT r.id FROM redirects r WHERE lower(r.alias) = lower(redirects.alias)
+        ORDER BY r.created_at, r.id LIMIT 1
+    ) THEN lower(alias)
+    ELSE lower(alias) || ':' || id
+END;
+
+CREATE UNIQUE INDEX idx_redirects_alias_key ON redirects(alias_key);
+
+-- once the redirect holding a key is renamed or purged, the oldest redirect waiting for it
+-- takes it over
+CREATE TRIGGER redirects_release_alias_key_update
+AFTER UPDATE OF alias ON redirects
+WHEN lower(NEW.alias) <> lower(OLD.alias)
+BEGIN
+    UPDATE redirects SET alias_key = lower(alias)
+    WHERE id = (
+        SELECT r.id FROM redirects r WHERE r.alias_key = lower(r.alias) || ':' || r.id
+        AND lower(r.alias) = lower(OLD.alias) ORDER BY r.created_at, r.id LIMIT 1
+    )
+    AND NOT EXISTS (SELECT 1 FROM redirects r WHERE r.alias_key = lower(OLD.alias));
+END;
+
+CREATE TRIGGER redirects_release_alias_key_delete
+AFTER DELETE ON redirects
+BEGIN
+    UPDATE redirects SET alias_key = lower(alias)
+    WHERE id = (
+        SELECT r.id FROM redirects r WHERE r.alias_key = lower(r.alias) || ':' || r.id
+        AND lower(r.alias) = lower(OLD.alias) ORDER BY r.created_at, r.id LIMIT 1
+    )
+    AND NOT EXISTS (SELECT 1 FROM redirects r WHERE r.alias_key = lower(OLD.alias));
+END;
@@ -0,0 +1,32 @@
+CREATE TABLE IF NOT EXISTS redirect_aliases (
+    alias TEXT NOT NULL PRIMARY KEY,
+    alias_key TEXT NOT NULL UNIQUE,
+    redirect_id TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY(redirect_id) REFERENCES redirects(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_redirect_aliases_redirect_id ON redirect_aliases(redirect_id);
+
+-- primary and secondary aliases share one namespace, the triggers keep both tables apart
+CREATE TRIGGER IF NOT EXISTS redirect_aliases_unique_insert
+BEFORE INSERT ON redirect_aliases
+WHEN EXISTS (SELECT 1 FROM redirects WHERE alias_key = NEW.alias_key OR alias = NEW.alias)
+BEGIN
+    SELECT RAISE(ABORT, 'alias is already taken');
+END;
+
+CREATE TRIGGER IF NOT EXISTS redirects_alias_unique_insert
+BEFORE INSERT ON redirects
+WHEN EXISTS (SELECT 1 FROM redirect_aliases WHERE alias_key = lower(NEW.alias))
+BEGIN
+    SELECT RAISE(ABORT, 'alias is already taken');
+END;
+
+CREATE TRIGGER IF NOT EXISTS redirects_alias_unique_update
+BEFORE UPDATE OF alias ON redirects
+WHEN NEW.alias <> OLD.alias
+AND EXISTS (SELECT 1 FROM redirect_aliases WHERE alias_key = lower(NEW.alias))
+BEGIN
+    SELECT RAISE(ABORT, 'alias is already taken');
+END;
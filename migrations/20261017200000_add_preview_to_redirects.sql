@@ -0,0 +1 @@
+ALTER TABLE redirects ADD COLUMN preview BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1 @@
+ALTER TABLE redirects ADD COLUMN pwhash TEXT;
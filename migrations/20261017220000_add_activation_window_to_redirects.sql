@@ -0,0 +1,3 @@
+ALTER TABLE redirects ADD COLUMN active_from INTEGER;
+ALTER TABLE redirects ADD COLUMN active_until INTEGER;
+ALTER TABLE redirects ADD COLUMN fallback_url TEXT;
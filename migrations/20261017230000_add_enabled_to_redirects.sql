@@ -0,0 +1,3 @@
+ALTER TABLE redirects ADD COLUMN enabled BOOLEAN NOT NULL DEFAULT TRUE;
+-- set when an admin disables the redirect, the owner can't enable it again
+ALTER TABLE redirects ADD COLUMN locked BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,20 @@
+CREATE TABLE IF NOT EXISTS redirect_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    redirect_id TEXT NOT NULL,
+    old_url TEXT NOT NULL,
+    new_url TEXT NOT NULL,
+    changed_by TEXT,
+    changed_at INTEGER NOT NULL,
+    FOREIGN KEY(redirect_id) REFERENCES redirects(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_redirect_history_redirect_id ON redirect_history(redirect_id);
+
+-- records every url change, regardless of which update changed it
+CREATE TRIGGER IF NOT EXISTS redirects_url_history
+AFTER UPDATE OF url ON redirects
+WHEN NEW.url <> OLD.url
+BEGIN
+    INSERT INTO redirect_history (redirect_id, old_url, new_url, changed_by, changed_at)
+    VALUES (NEW.id, OLD.url, NEW.url, NEW.updated_by, NEW.updated_at);
+END;
@@ -0,0 +1,6 @@
+-- deleted rows stay in the trash until they are purged, so their aliases and names stay taken
+ALTER TABLE redirects ADD COLUMN deleted_at INTEGER;
+ALTER TABLE users ADD COLUMN deleted_at INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_redirects_deleted_at ON redirects(deleted_at);
+CREATE INDEX IF NOT EXISTS idx_users_deleted_at ON users(deleted_at);
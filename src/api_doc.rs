@@ -9,9 +9,15 @@ use utoipa_swagger_ui::SwaggerUi;
 
 use crate::controller::metrics;
 use crate::model::{
-    FullRedirectListDTO, PasswordChangeDataDTO, Redirect, RedirectDTO, RedirectListDTO,
-    UpdateUrlDTO, UserCredentialsDTO, UserRegistrationDTO, UserRegistrationTokenDTO, UserTokenDTO,
+    BulkAction, BulkFilterDTO, BulkItemResultDTO, BulkItemStatus, BulkOperationDTO, BulkResultDTO,
+    ClickBucketDTO, ClickHistogramDTO, ExportFormat, FullRedirectListDTO, Granularity,
+    ImportReportDTO, ImportRowResultDTO, ImportRowStatus, PasswordChangeDataDTO, Redirect,
+    RedirectDTO, RedirectHistoryDTO, RedirectHistoryEntryDTO, RedirectListDTO, RedirectPasswordDTO,
+    RedirectSortKey, RedirectState, RedirectStatsDTO, RedirectType, SortOrder, TrashedUserDTO,
+    TrashedUserListDTO, UpdateRedirectDTO, UserCredentialsDTO, UserRegistrationDTO,
+    UserRegistrationTokenDTO, UserTokenDTO,
 };
+use crate::service::ValidationErrorResponse;
 use crate::{controller::admin, model::UserDTO};
 use crate::{controller::login, model::DeletedUserDTO};
 use crate::{controller::redirect, model::DeletedUserResourceDTO};
@@ -40,25 +46,58 @@ use crate::{health_check, model::SimpleUserDTO};
         login::login_user_handler,
         admin::request_user_registration_token_handler,
         admin::get_all_redirects_admin_handler,
+        admin::export_all_redirects_admin_handler,
+        admin::bulk_update_redirects_admin_handler,
+        admin::get_trashed_redirects_admin_handler,
         admin::delete_redirect_admin_handler,
+        admin::restore_redirect_admin_handler,
+        admin::enable_redirect_admin_handler,
+        admin::disable_redirect_admin_handler,
         admin::user_info_admin_handler,
         admin::all_users_info_admin_handler,
         admin::delete_user_admin_handler,
+        admin::trashed_users_admin_handler,
+        admin::restore_user_admin_handler,
+        admin::global_analytics_admin_handler,
         user::register_user_handler,
         user::simple_user_info_handler,
         user::change_user_password_handler,
         redirect::create_redirect_handler,
         redirect::get_all_user_redirects_handler,
+        redirect::import_redirects_handler,
+        redirect::export_redirects_handler,
+        redirect::bulk_update_redirects_handler,
         redirect::update_redirect_handler,
         redirect::delete_redirect_handler,
+        redirect::get_trashed_redirects_handler,
+        redirect::restore_redirect_handler,
         redirect::follow_redirect_handler,
+        redirect::follow_redirect_with_path_handler,
+        redirect::unlock_redirect_handler,
+        redirect::unlock_redirect_with_path_handler,
+        redirect::get_redirect_stats_handler,
+        redirect::get_redirect_analytics_handler,
+        redirect::add_redirect_tag_handler,
+        redirect::remove_redirect_tag_handler,
+        redirect::add_redirect_alias_handler,
+        redirect::remove_redirect_alias_handler,
+        redirect::get_redirect_history_handler,
+        redirect::rollback_redirect_handler,
+        redirect::enable_redirect_handler,
+        redirect::disable_redirect_handler,
         health_check::health_check_handler,
         metrics::metrics_handler,
     ),
     components(schemas(
         UserDTO, DeletedUserDTO, DeletedUserResourceDTO, SimpleUserDTO, UserListDTO, UserCredentialsDTO,
+        TrashedUserDTO, TrashedUserListDTO,
         PasswordChangeDataDTO, UserTokenDTO, UserRegistrationTokenDTO, UserRegistrationDTO,
-        Redirect, FullRedirectListDTO, RedirectDTO, RedirectListDTO, UpdateUrlDTO
+        Redirect, FullRedirectListDTO, RedirectDTO, RedirectListDTO, UpdateRedirectDTO, RedirectStatsDTO, RedirectType, RedirectState,
+        RedirectHistoryDTO, RedirectHistoryEntryDTO,
+        RedirectSortKey, SortOrder, ImportReportDTO, ImportRowResultDTO, ImportRowStatus,
+        ValidationErrorResponse, ExportFormat, BulkAction, BulkFilterDTO, BulkOperationDTO,
+        BulkItemStatus, BulkItemResultDTO, BulkResultDTO, ClickBucketDTO, ClickHistogramDTO, Granularity,
+        RedirectPasswordDTO
     )),
     modifiers(&SecurityAddon)
 )]
@@ -1,14 +1,20 @@
 use axum::{
-    Json, Router,
-    extract::{Path, State},
+    Extension, Json, Router,
+    extract::{Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
-    routing::{delete, get},
+    routing::{delete, get, post},
 };
 
 use crate::{
-    AppContext, middleware,
-    model::{DeletedUserDTO, FullRedirectListDTO, UserDTO, UserListDTO, UserRegistrationTokenDTO},
+    AppContext,
+    controller::redirect,
+    middleware,
+    model::{
+        AnalyticsQueryDTO, BulkOperationDTO, BulkResultDTO, ClickHistogramDTO, DeletedUserDTO,
+        ExportQueryDTO, FullRedirectListDTO, Redirect, RedirectListQueryDTO, TrashedUserListDTO,
+        UserClaimsDTO, UserDTO, UserListDTO, UserRegistrationTokenDTO,
+    },
     service::DbServiceError,
 };
 
@@ -19,13 +25,43 @@ pub(crate) fn router() -> Router<AppContext> {
             get(request_user_registration_token_handler),
         )
         .route("/api/admin/redirects", get(get_all_redirects_admin_handler))
+        .route(
+            "/api/admin/redirects/export",
+            get(export_all_redirects_admin_handler),
+        )
+        .route(
+            "/api/admin/redirects/bulk",
+            post(bulk_update_redirects_admin_handler),
+        )
+        .route(
+            "/api/admin/redirects/trash",
+            get(get_trashed_redirects_admin_handler),
+        )
         .route(
             "/api/admin/redirects/{id}",
             delete(delete_redirect_admin_handler),
         )
+        .route(
+            "/api/admin/redirects/{id}/restore",
+            post(restore_redirect_admin_handler),
+        )
+        .route(
+            "/api/admin/redirects/{id}/enable",
+            post(enable_redirect_admin_handler),
+        )
+        .route(
+            "/api/admin/redirects/{id}/disable",
+            post(disable_redirect_admin_handler),
+        )
+        .route("/api/admin/users/trash", get(trashed_users_admin_handler))
         .route("/api/admin/users/{id}", get(user_info_admin_handler))
         .route("/api/admin/users/{id}", delete(delete_user_admin_handler))
+        .route(
+            "/api/admin/users/{id}/restore",
+            post(restore_user_admin_handler),
+        )
         .route("/api/admin/users", get(all_users_info_admin_handler))
+        .route("/api/admin/analytics", get(global_analytics_admin_handler))
         .layer(axum::middleware::from_fn(middleware::is_admin_middleware))
 }
 
@@ -58,22 +94,77 @@ async fn request_user_registration_token_handler(
     path = "/api/admin/redirects",
     tag = "Admin",
     summary = "Get all redirects",
-    description = "Returns a list of all currently created redirects. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    description = "Returns a page of all currently created redirects. If more redirects are available, the response contains a `next_cursor` which can be passed as `cursor` to fetch the next page. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    params(RedirectListQueryDTO),
     security(("bearer_auth" = [])),
     operation_id="get_all_redirects",
     responses(
         (status = StatusCode::OK, description = "Success. Returns a list of all currently created redirects", body = FullRedirectListDTO),
+        (status = StatusCode::BAD_REQUEST, description = "Bad Request. Limit or cursor are invalid."),
         (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
         (status = StatusCode::FORBIDDEN, description = "Forbidden. User authorized but doesn't have permission.")
 ))]
 async fn get_all_redirects_admin_handler(
     State(app_context): State<AppContext>,
+    Query(query): Query<RedirectListQueryDTO>,
+) -> Result<impl IntoResponse, DbServiceError> {
+    let redirects = app_context
+        .redirect_service
+        .get_all_redirects(&query)
+        .await?;
+    Ok((StatusCode::OK, Json(redirects)).into_response())
+}
+
+#[utoipa::path(get,
+    path = "/api/admin/redirects/export",
+    tag = "Admin",
+    summary = "Export all redirects",
+    description = "Exports the redirects of all users as JSON, CSV or a Netscape bookmarks file. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    params(ExportQueryDTO),
+    security(("bearer_auth" = [])),
+    operation_id="export_all_redirects",
+    responses(
+        (status = StatusCode::OK, description = "Success. Returns the exported redirects as attachment.", content(
+            (Vec<Redirect> = "application/json"),
+            (String = "text/csv"),
+            (String = "text/html"),
+        )),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authorized but doesn't have permission.")
+))]
+async fn export_all_redirects_admin_handler(
+    State(app_context): State<AppContext>,
+    Query(query): Query<ExportQueryDTO>,
 ) -> impl IntoResponse {
-    let res = app_context.redirect_service.get_all_redirects().await;
-    match res {
-        Ok(t) => (StatusCode::OK, Json(t)).into_response(),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(e)).into_response(),
-    }
+    let format = query.format.unwrap_or_default();
+    let stream = app_context.export_service.export_redirects(None, format);
+    redirect::export_response(stream, format)
+}
+
+#[utoipa::path(post,
+    path = "/api/admin/redirects/bulk",
+    tag = "Admin",
+    summary = "Bulk update redirects of all users",
+    description = "Deletes, tags, untags or repoints many redirects of any user at once. The redirects are selected either by a list of `aliases` or by a `filter` which matches redirects of all users. The response contains the result of every item. At most 1000 redirects can be changed at once. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    request_body = BulkOperationDTO,
+    security(("bearer_auth" = [])),
+    operation_id="bulk_update_all_redirects",
+    responses(
+        (status = StatusCode::OK, description = "Success. Returns the result of every item.", body = BulkResultDTO),
+        (status = StatusCode::BAD_REQUEST, description = "Bad Request. The selection is missing or too large, or the tag or url don't match requirements."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authorized but doesn't have permission.")
+))]
+async fn bulk_update_redirects_admin_handler(
+    State(app_context): State<AppContext>,
+    Extension(user_claims): Extension<UserClaimsDTO>,
+    Json(payload): Json<BulkOperationDTO>,
+) -> Result<impl IntoResponse, DbServiceError> {
+    let result = app_context
+        .redirect_service
+        .bulk_update_redirects(&payload, &user_claims.user_id)
+        .await?;
+    Ok((StatusCode::OK, Json(result)).into_response())
 }
 
 #[utoipa::path(delete,
@@ -83,11 +174,11 @@ async fn get_all_redirects_admin_handler(
     ),
     tag = "Admin",
     summary = "Delete redirect via id",
-    description = "Deletes a redirect via its id. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    description = "Moves a redirect of any user to the trash via its id. It is purged after the retention period unless it is restored. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
     security(("bearer_auth" = [])),
     operation_id="delete_redirect_by_id",
     responses(
-        (status = StatusCode::NO_CONTENT, description = "No Content. Deletes a redirect"),
+        (status = StatusCode::NO_CONTENT, description = "No Content. Redirect moved to the trash."),
         (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect doesn't exist."),
         (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
         (status = StatusCode::FORBIDDEN, description = "Forbidden. User authorized but doesn't have permission.")
@@ -107,6 +198,114 @@ async fn delete_redirect_admin_handler(
     }
 }
 
+#[utoipa::path(get,
+    path = "/api/admin/redirects/trash",
+    tag = "Admin",
+    summary = "Get all trashed redirects",
+    description = "Returns a page of the deleted redirects of all users which have not been purged yet. If more redirects are available, the response contains a `next_cursor` which can be passed as `cursor` to fetch the next page. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    params(RedirectListQueryDTO),
+    security(("bearer_auth" = [])),
+    operation_id="get_all_trashed_redirects",
+    responses(
+        (status = StatusCode::OK, description = "Success. Returns a list of trashed redirects", body = FullRedirectListDTO),
+        (status = StatusCode::BAD_REQUEST, description = "Bad Request. Limit or cursor are invalid."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authorized but doesn't have permission.")
+))]
+async fn get_trashed_redirects_admin_handler(
+    State(app_context): State<AppContext>,
+    Query(query): Query<RedirectListQueryDTO>,
+) -> Result<impl IntoResponse, DbServiceError> {
+    let redirects = app_context
+        .redirect_service
+        .get_all_trashed_redirects(&query)
+        .await?;
+    Ok((StatusCode::OK, Json(redirects)).into_response())
+}
+
+#[utoipa::path(post,
+    path = "/api/admin/redirects/{id}/restore",
+    params(
+        ("id" = String, Path, description = "The redirect id."),
+    ),
+    tag = "Admin",
+    summary = "Restore redirect via id",
+    description = "Restores a redirect of any user from the trash via its id. Redirects of a trashed user can only be restored together with their owner. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    security(("bearer_auth" = [])),
+    operation_id="restore_redirect_by_id",
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "No Content. Redirect restored."),
+        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect isn't in the trash or its owner is trashed."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authorized but doesn't have permission.")
+))]
+async fn restore_redirect_admin_handler(
+    State(app_context): State<AppContext>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, DbServiceError> {
+    app_context
+        .redirect_service
+        .restore_redirect_by_id(&id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(post,
+    path = "/api/admin/redirects/{id}/enable",
+    params(
+        ("id" = String, Path, description = "The redirect id."),
+    ),
+    tag = "Admin",
+    summary = "Enable redirect via id",
+    description = "Enables a redirect of any user via its id and lifts the lock of a redirect disabled by an admin. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    security(("bearer_auth" = [])),
+    operation_id="enable_redirect_by_id",
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "No Content. Redirect enabled."),
+        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect doesn't exist."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authorized but doesn't have permission.")
+))]
+async fn enable_redirect_admin_handler(
+    State(app_context): State<AppContext>,
+    Path(id): Path<String>,
+    Extension(user_claims): Extension<UserClaimsDTO>,
+) -> Result<impl IntoResponse, DbServiceError> {
+    app_context
+        .redirect_service
+        .set_redirect_enabled_by_id(&id, true, &user_claims.user_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(post,
+    path = "/api/admin/redirects/{id}/disable",
+    params(
+        ("id" = String, Path, description = "The redirect id."),
+    ),
+    tag = "Admin",
+    summary = "Disable redirect via id",
+    description = "Disables a redirect of any user via its id, e.g. to stop an abusive link. The redirect is locked, so its owner can't enable it again. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    security(("bearer_auth" = [])),
+    operation_id="disable_redirect_by_id",
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "No Content. Redirect disabled."),
+        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect doesn't exist."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authorized but doesn't have permission.")
+))]
+async fn disable_redirect_admin_handler(
+    State(app_context): State<AppContext>,
+    Path(id): Path<String>,
+    Extension(user_claims): Extension<UserClaimsDTO>,
+) -> Result<impl IntoResponse, DbServiceError> {
+    app_context
+        .redirect_service
+        .set_redirect_enabled_by_id(&id, false, &user_claims.user_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
 #[utoipa::path(get,
     path = "/api/admin/users/{id}",
     params(
@@ -162,11 +361,11 @@ async fn all_users_info_admin_handler(State(app_context): State<AppContext>) ->
     ),
     tag = "Admin",
     summary = "Delete a user",
-    description = "Deletes a user via its uiserid. This will also delete all of the users registered redirects. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    description = "Moves a user and all of their redirects to the trash via the userid. They are purged after the retention period unless the user is restored. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
     security(("bearer_auth" = [])),
     operation_id="delete_user",
     responses(
-        (status = StatusCode::OK, description = "Ok. Returns userid and amount of deleted redirects", body = DeletedUserDTO),
+        (status = StatusCode::OK, description = "Ok. Returns userid and amount of trashed redirects", body = DeletedUserDTO),
         (status = StatusCode::NOT_FOUND, description = "Not Found. User doesn't exist."),
         (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
         (status = StatusCode::FORBIDDEN, description = "Forbidden. User authorized but doesn't have permission.")
@@ -182,3 +381,71 @@ async fn delete_user_admin_handler(
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(e)).into_response(),
     }
 }
+
+#[utoipa::path(get,
+    path = "/api/admin/users/trash",
+    tag = "Admin",
+    summary = "Get trashed users",
+    description = "Returns a list of the deleted users which have not been purged yet. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    security(("bearer_auth" = [])),
+    operation_id="get_trashed_users_admin",
+    responses(
+        (status = StatusCode::OK, description = "Ok. Returns list of trashed users.", body = TrashedUserListDTO),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authorized but doesn't have permission.")
+))]
+async fn trashed_users_admin_handler(
+    State(app_context): State<AppContext>,
+) -> Result<impl IntoResponse, DbServiceError> {
+    let users = app_context.user_service.get_trashed_users().await?;
+    Ok((StatusCode::OK, Json(users)).into_response())
+}
+
+#[utoipa::path(post,
+    path = "/api/admin/users/{id}/restore",
+    params(
+        ("id" = String, Path, description = "The user id."),
+    ),
+    tag = "Admin",
+    summary = "Restore a user",
+    description = "Restores a user from the trash together with the redirects which were deleted along with the user. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    security(("bearer_auth" = [])),
+    operation_id="restore_user",
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "No Content. User restored."),
+        (status = StatusCode::NOT_FOUND, description = "Not Found. User isn't in the trash."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authorized but doesn't have permission.")
+))]
+async fn restore_user_admin_handler(
+    State(app_context): State<AppContext>,
+    Path(user_id): Path<String>,
+) -> Result<impl IntoResponse, DbServiceError> {
+    app_context.user_service.restore_user(&user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(get,
+    path = "/api/admin/analytics",
+    params(AnalyticsQueryDTO),
+    tag = "Admin",
+    summary = "Get global analytics",
+    description = "Returns the clicks of all redirects aggregated into hourly or daily buckets. Empty buckets are included. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    security(("bearer_auth" = [])),
+    operation_id="get_global_analytics_admin",
+    responses(
+        (status = StatusCode::OK, description = "Ok. Returns the click histogram of all redirects.", body = ClickHistogramDTO),
+        (status = StatusCode::BAD_REQUEST, description = "Bad Request. The requested range is invalid."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authorized but doesn't have permission.")
+))]
+async fn global_analytics_admin_handler(
+    State(app_context): State<AppContext>,
+    Query(query): Query<AnalyticsQueryDTO>,
+) -> Result<impl IntoResponse, DbServiceError> {
+    let histogram = app_context
+        .analytics_service
+        .get_global_histogram(&query)
+        .await?;
+    Ok((StatusCode::OK, Json(histogram)).into_response())
+}
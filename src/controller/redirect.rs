@@ -1,17 +1,25 @@
+use std::net::SocketAddr;
+
 use axum::{
     Extension, Json, Router,
-    extract::{Path, State},
-    http::StatusCode,
-    response::{IntoResponse, Redirect, Response},
-    routing::{delete, get, patch, post},
+    body::{Body, Bytes},
+    extract::{ConnectInfo, Form, Path, Query, RawQuery, State},
+    http::{HeaderMap, StatusCode, Uri, header},
+    response::{Html, IntoResponse, Response},
+    routing::{delete, get, patch, post, put},
 };
 
 use crate::{
-    AppContext,
-    model::{RedirectCreationDTO, RedirectDTO, RedirectListDTO, UserClaimsDTO},
-    service::ValidationErrorResponse,
+    AppContext, Fallback,
+    model::{
+        AnalyticsQueryDTO, BulkOperationDTO, BulkResultDTO, ClickEvent, ClickHistogramDTO,
+        ExportFormat, ExportQueryDTO, ImportFormat, ImportQueryDTO, ImportReportDTO, Redirect,
+        RedirectCreationDTO, RedirectDTO, RedirectHistoryDTO, RedirectListDTO,
+        RedirectListQueryDTO, RedirectPasswordDTO, RedirectStatsDTO, RedirectType, UserClaimsDTO,
+    },
+    service::{ValidationErrorResponse, escape_html},
 };
-use crate::{model::UpdateUrlDTO, service::DbServiceError};
+use crate::{model::UpdateRedirectDTO, service::DbServiceError};
 
 impl IntoResponse for ValidationErrorResponse {
     fn into_response(self) -> axum::response::Response {
@@ -23,8 +31,207 @@ pub(crate) fn router() -> Router<AppContext> {
     Router::new()
         .route("/api/redirects", post(create_redirect_handler))
         .route("/api/redirects", get(get_all_user_redirects_handler))
+        .route("/api/redirects/import", post(import_redirects_handler))
+        .route("/api/redirects/export", get(export_redirects_handler))
+        .route("/api/redirects/bulk", post(bulk_update_redirects_handler))
+        .route("/api/redirects/trash", get(get_trashed_redirects_handler))
+        .route(
+            "/api/redirects/trash/{alias}/restore",
+            post(restore_redirect_handler),
+        )
         .route("/api/redirects/{alias}", patch(update_redirect_handler))
         .route("/api/redirects/{alias}", delete(delete_redirect_handler))
+        .route(
+            "/api/redirects/{alias}/stats",
+            get(get_redirect_stats_handler),
+        )
+        .route(
+            "/api/redirects/{alias}/analytics",
+            get(get_redirect_analytics_handler),
+        )
+        .route(
+            "/api/redirects/{alias}/tags/{tag}",
+            put(add_redirect_tag_handler),
+        )
+        .route(
+            "/api/redirects/{alias}/tags/{tag}",
+            delete(remove_redirect_tag_handler),
+        )
+        .route(
+            "/api/redirects/{alias}/aliases/{secondary_alias}",
+            put(add_redirect_alias_handler),
+        )
+        .route(
+            "/api/redirects/{alias}/aliases/{secondary_alias}",
+            delete(remove_redirect_alias_handler),
+        )
+        .route(
+            "/api/redirects/{alias}/history",
+            get(get_redirect_history_handler),
+        )
+        .route(
+            "/api/redirects/{alias}/history/{id}/rollback",
+            post(rollback_redirect_handler),
+        )
+        .route(
+            "/api/redirects/{alias}/enable",
+            post(enable_redirect_handler),
+        )
+        .route(
+            "/api/redirects/{alias}/disable",
+            post(disable_redirect_handler),
+        )
+}
+
+fn redirect_status(redirect_type: RedirectType) -> StatusCode {
+    match redirect_type {
+        RedirectType::MovedPermanently => StatusCode::MOVED_PERMANENTLY,
+        RedirectType::Found => StatusCode::FOUND,
+        RedirectType::TemporaryRedirect => StatusCode::TEMPORARY_REDIRECT,
+        RedirectType::PermanentRedirect => StatusCode::PERMANENT_REDIRECT,
+    }
+}
+
+pub(crate) fn export_response(
+    stream: futures_util::stream::BoxStream<'static, Result<String, DbServiceError>>,
+    format: ExportFormat,
+) -> Response {
+    let (content_type, file_name) = match format {
+        ExportFormat::Json => ("application/json", "redirects.json"),
+        ExportFormat::Csv => ("text/csv; charset=utf-8", "redirects.csv"),
+        ExportFormat::Bookmarks => ("text/html; charset=utf-8", "bookmarks.html"),
+    };
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_owned()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{file_name}\""),
+            ),
+        ],
+        Body::from_stream(stream),
+    )
+        .into_response()
+}
+
+// converts a unix timestamp to a UTC date, see http://howardhinnant.github.io/date_algorithms.html
+fn format_date(timestamp: i64) -> String {
+    let days = timestamp.div_euclid(86_400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+fn render_page(title: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta name="robots" content="noindex">
+<title>Via-Alias: {title}</title>
+<style>
+body {{ font-family: sans-serif; max-width: 40rem; margin: 4rem auto; padding: 0 1rem; color: #222; }}
+dt {{ font-weight: bold; margin-top: 1rem; }}
+dd {{ margin: 0.25rem 0 0; overflow-wrap: anywhere; }}
+input {{ display: block; width: 100%; box-sizing: border-box; margin-top: 0.5rem; padding: 0.5rem; }}
+a, button {{ display: inline-block; margin-top: 2rem; padding: 0.5rem 1rem; border: none; border-radius: 4px; background: #224; color: #fff; font-size: 1rem; text-decoration: none; }}
+.error {{ color: #a00; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+{body}</body>
+</html>
+"#
+    )
+}
+
+fn render_preview(redirect: &Redirect, owner: &str) -> String {
+    let url = escape_html(&redirect.url);
+    let body = format!(
+        r#"<p>This link leads to the destination below. Check it before you continue.</p>
+<dl>
+<dt>Destination</dt>
+<dd><code>{url}</code></dd>
+<dt>Created by</dt>
+<dd>{owner}</dd>
+<dt>Created on</dt>
+<dd>{created}</dd>
+</dl>
+<a href="{url}" rel="noopener noreferrer nofollow">Continue</a>
+"#,
+        owner = escape_html(owner),
+        created = format_date(redirect.created_at),
+    );
+    render_page(&escape_html(&redirect.alias), &body)
+}
+
+// the form has no action, so it is sent to the requested url including path and query
+fn render_password_form(alias: &str, error: Option<&str>) -> String {
+    let error = error.map_or(String::new(), |e| {
+        format!("<p class=\"error\">{}</p>\n", escape_html(e))
+    });
+    let body = format!(
+        r#"<p>This link is protected. Enter the password to continue.</p>
+{error}<form method="post">
+<label for="password">Password</label>
+<input id="password" name="password" type="password" required autofocus>
+<button type="submit">Continue</button>
+</form>
+"#
+    );
+    render_page(&escape_html(alias), &body)
+}
+
+fn password_form_response(status: StatusCode, alias: &str, error: Option<&str>) -> Response {
+    (status, Html(render_password_form(alias, error))).into_response()
+}
+
+fn fallback_response(fallback: &Fallback, error: DbServiceError, status: StatusCode) -> Response {
+    match fallback {
+        Fallback::Status => error.into_response(),
+        Fallback::Redirect(url) => (status, [(header::LOCATION, url.clone())]).into_response(),
+        Fallback::Page(page) => {
+            (error.into_response().status(), Html(page.to_string())).into_response()
+        }
+    }
+}
+
+// behind a reverse proxy every visitor shares the proxy's address, the proxy appends the
+// visitor's address as the last entry of X-Forwarded-For
+fn client_address(trust_forwarded_for: bool, socket: SocketAddr, headers: &HeaderMap) -> String {
+    trust_forwarded_for
+        .then(|| header_value(headers, header::HeaderName::from_static("x-forwarded-for")))
+        .flatten()
+        .and_then(|forwarded| {
+            forwarded
+                .rsplit(',')
+                .next()
+                .map(str::trim)
+                .filter(|client| !client.is_empty())
+                .map(str::to_owned)
+        })
+        .unwrap_or_else(|| socket.ip().to_string())
+}
+
+fn header_value(headers: &HeaderMap, name: header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.chars().take(512).collect())
 }
 
 #[utoipa::path(delete,
@@ -32,13 +239,13 @@ pub(crate) fn router() -> Router<AppContext> {
     tag = "Redirects",
     security(("bearer_auth" = [])),
     summary = "Delete redirect",
-    description = "Deletes a redirect via its alias. Users can only delete redirects they have created. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    description = "Moves a redirect to the trash via its alias. Trashed redirects can't be followed and are purged after the retention period, until then their aliases stay taken. Users can only delete redirects they have created. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
     params(
         ("alias" = String, Path, description = "The redirect alias."),
     ),
     operation_id="delete_redirect",
     responses(
-        (status = StatusCode::NO_CONTENT, description = "No Content. Redirect deleted successfully."),
+        (status = StatusCode::NO_CONTENT, description = "No Content. Redirect moved to the trash."),
         (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
         (status = StatusCode::FORBIDDEN, description = "Forbidden. User authorized but doesn't have permission.")
 ))]
@@ -53,24 +260,80 @@ async fn delete_redirect_handler(
         .await?;
     Ok(StatusCode::NO_CONTENT.into_response())
 }
+#[utoipa::path(get,
+    path = "/api/redirects/trash",
+    tag = "Redirects",
+    security(("bearer_auth" = [])),
+    summary = "Get trashed redirects",
+    description = "Returns a page of the deleted redirects of the current user which have not been purged yet. If more redirects are available, the response contains a `next_cursor` which can be passed as `cursor` to fetch the next page. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    params(RedirectListQueryDTO),
+    operation_id="get_user_trashed_redirects_list",
+    responses(
+        (status = StatusCode::OK, description = "Ok. Returns list of trashed redirects.", body = RedirectListDTO),
+        (status = StatusCode::BAD_REQUEST, description = "Bad Request. Limit or cursor are invalid."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+))]
+async fn get_trashed_redirects_handler(
+    State(app_state): State<AppContext>,
+    Extension(user_claims): Extension<UserClaimsDTO>,
+    Query(query): Query<RedirectListQueryDTO>,
+) -> Result<impl IntoResponse, DbServiceError> {
+    let redirects = app_state
+        .redirect_service
+        .get_user_trashed_redirects(&user_claims.user_id, &query)
+        .await?;
+    Ok(Json(redirects).into_response())
+}
+
+#[utoipa::path(post,
+    path = "/api/redirects/trash/{alias}/restore",
+    tag = "Redirects",
+    security(("bearer_auth" = [])),
+    summary = "Restore redirect",
+    description = "Restores a deleted redirect from the trash via its alias. Users can only restore redirects they have created. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    params(
+        ("alias" = String, Path, description = "The redirect alias."),
+    ),
+    operation_id="restore_redirect",
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "No Content. Redirect restored."),
+        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect isn't in the trash."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authorized but doesn't have permission.")
+))]
+async fn restore_redirect_handler(
+    State(app_state): State<AppContext>,
+    Path(alias): Path<String>,
+    Extension(user_claims): Extension<UserClaimsDTO>,
+) -> Result<Response, DbServiceError> {
+    app_state
+        .redirect_service
+        .restore_user_redirect(&alias, &user_claims.user_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
 #[utoipa::path(get,
     path = "/api/redirects",
     tag = "Redirects",
     security(("bearer_auth" = [])),
     summary = "Get redirects",
-    description = "Returns a list of all redirects owned by the current user. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    description = "Returns a page of the redirects owned by the current user. If more redirects are available, the response contains a `next_cursor` which can be passed as `cursor` to fetch the next page. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    params(RedirectListQueryDTO),
     operation_id="get_user_redirects_list",
     responses(
         (status = StatusCode::OK, description = "Ok. Returns list of redirects.", body = RedirectListDTO),
+        (status = StatusCode::BAD_REQUEST, description = "Bad Request. Limit or cursor are invalid."),
         (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
 ))]
 async fn get_all_user_redirects_handler(
     State(app_state): State<AppContext>,
     Extension(user_claims): Extension<UserClaimsDTO>,
+    Query(query): Query<RedirectListQueryDTO>,
 ) -> Result<impl IntoResponse, DbServiceError> {
     let redirects = app_state
         .redirect_service
-        .get_all_user_redirects(&user_claims.user_id)
+        .get_all_user_redirects(&user_claims.user_id, &query)
         .await?;
     Ok(Json(redirects).into_response())
 }
@@ -79,12 +342,12 @@ async fn get_all_user_redirects_handler(
     path = "/api/redirects",
     tag = "Redirects",
     summary = "Create redirect",
-    description = "Creates a new redirect. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    description = "Creates a new redirect. If no `alias` is supplied, a random short alias is generated and returned in the response. An optional `expires_at` unix timestamp limits the lifetime of the redirect, expired redirects respond with `410 Gone` for the trash retention period before they are moved to the trash. An optional `max_clicks` limits how often the redirect can be followed before it responds with `410 Gone`. With `template` enabled the url can contain the placeholders `{1}`, `{2}`, ..., `{path}` and `{query}` after the host, which are filled when the redirect is followed. Optional `query_params` like UTM parameters are added to the url when the redirect is followed, unless the url already contains a parameter with the same name. Aliases are unique regardless of case. `case_insensitive` overrides the server default for resolving the alias regardless of case when the redirect is followed. With `preview` enabled, following the redirect shows a page with the destination instead of redirecting. With a `password`, the password has to be entered before the redirect is followed. `active_from` and `active_until` limit when the redirect can be followed, outside of that window and once it expired or reached its click limit it redirects to the optional `fallback_url` instead. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
     security(("bearer_auth" = [])),
     request_body = RedirectDTO,
     operation_id="create_redirect",
     responses(
-        (status = StatusCode::CREATED, description = "Created. Returns list of redirects.", body = RedirectDTO),
+        (status = StatusCode::CREATED, description = "Created. Returns the created redirect.", body = RedirectDTO),
         (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
         (status = StatusCode::BAD_REQUEST, description = "Alias or url don't match requirements."),
         (status = StatusCode::CONFLICT, description = "A redirect with that alias already exists.")
@@ -95,48 +358,692 @@ async fn create_redirect_handler(
     Json(payload): Json<RedirectDTO>,
 ) -> Result<impl IntoResponse, DbServiceError> {
     let redirect_creation = RedirectCreationDTO {
-        redirect: payload.clone(),
+        redirect: payload,
         owner: user_claims.user_id,
     };
-    app_state
+    let created = app_state
         .redirect_service
         .create_redirect(&redirect_creation)
         .await?;
-    Ok((StatusCode::CREATED, Json(payload)).into_response())
+    Ok((StatusCode::CREATED, Json(created)).into_response())
+}
+
+#[utoipa::path(post,
+    path = "/api/redirects/import",
+    tag = "Redirects",
+    summary = "Import redirects",
+    description = "Creates many redirects at once from a JSON array or a CSV file with a header row. Both formats use the fields of the create endpoint, at least `alias` and `url` are required. All rows are validated and valid rows are inserted in a single transaction. Rows whose alias is already taken are reported as conflicts and skipped. With `dry_run=true` the report is generated without creating any redirects. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    params(ImportQueryDTO),
+    request_body(content(
+        (Vec<RedirectDTO> = "application/json"),
+        (String = "text/csv", example = "alias,url\ngh,https://github.com\ndocs,https://docs.rs"),
+    )),
+    security(("bearer_auth" = [])),
+    operation_id="import_redirects",
+    responses(
+        (status = StatusCode::OK, description = "Ok. Returns a report with the result of every row.", body = ImportReportDTO),
+        (status = StatusCode::BAD_REQUEST, description = "Bad Request. The body is not a JSON array or contains too many rows."),
+        (status = StatusCode::UNSUPPORTED_MEDIA_TYPE, description = "Unsupported Media Type. Content type has to be `application/json` or `text/csv`."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+))]
+async fn import_redirects_handler(
+    State(app_state): State<AppContext>,
+    Extension(user_claims): Extension<UserClaimsDTO>,
+    Query(query): Query<ImportQueryDTO>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, DbServiceError> {
+    let format = match headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+    {
+        Some(content_type) if content_type.starts_with("text/csv") => ImportFormat::Csv,
+        Some(content_type) if content_type.starts_with("application/json") => ImportFormat::Json,
+        _ => return Ok(StatusCode::UNSUPPORTED_MEDIA_TYPE.into_response()),
+    };
+    let report = app_state
+        .redirect_service
+        .import_redirects(
+            &user_claims.user_id,
+            format,
+            &body,
+            query.dry_run.unwrap_or_default(),
+        )
+        .await?;
+    Ok((StatusCode::OK, Json(report)).into_response())
+}
+
+#[utoipa::path(get,
+    path = "/api/redirects/export",
+    tag = "Redirects",
+    summary = "Export redirects",
+    description = "Exports all redirects owned by the current user as JSON, CSV or a Netscape bookmarks file. The JSON and CSV exports can be imported again via `POST /api/redirects/import`, protected redirects need a new `password` for that. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    params(ExportQueryDTO),
+    security(("bearer_auth" = [])),
+    operation_id="export_redirects",
+    responses(
+        (status = StatusCode::OK, description = "Ok. Returns the exported redirects as attachment.", content(
+            (Vec<Redirect> = "application/json"),
+            (String = "text/csv"),
+            (String = "text/html"),
+        )),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+))]
+async fn export_redirects_handler(
+    State(app_state): State<AppContext>,
+    Extension(user_claims): Extension<UserClaimsDTO>,
+    Query(query): Query<ExportQueryDTO>,
+) -> Response {
+    let format = query.format.unwrap_or_default();
+    let stream = app_state
+        .export_service
+        .export_redirects(Some(user_claims.user_id), format);
+    export_response(stream, format)
+}
+
+#[utoipa::path(post,
+    path = "/api/redirects/bulk",
+    tag = "Redirects",
+    summary = "Bulk update redirects",
+    description = "Deletes, tags, untags or repoints many redirects at once. The redirects are selected either by a list of `aliases` or by a `filter`, a filter only matches redirects of the current user. Every redirect is changed on its own and the response contains the result of every item. Users can only change redirects they have created, other redirects are reported as `forbidden`. At most 1000 redirects can be changed at once. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    request_body = BulkOperationDTO,
+    security(("bearer_auth" = [])),
+    operation_id="bulk_update_redirects",
+    responses(
+        (status = StatusCode::OK, description = "Ok. Returns the result of every item.", body = BulkResultDTO),
+        (status = StatusCode::BAD_REQUEST, description = "Bad Request. The selection is missing or too large, or the tag or url don't match requirements."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+))]
+async fn bulk_update_redirects_handler(
+    State(app_state): State<AppContext>,
+    Extension(user_claims): Extension<UserClaimsDTO>,
+    Json(payload): Json<BulkOperationDTO>,
+) -> Result<impl IntoResponse, DbServiceError> {
+    let result = app_state
+        .redirect_service
+        .bulk_update_user_redirects(&payload, &user_claims.user_id)
+        .await?;
+    Ok((StatusCode::OK, Json(result)).into_response())
 }
 
 #[utoipa::path(get,
     path = "/{alias}",
     tag = "Redirects",
     summary = "Follow redirect",
-    description = "Returns a tomporary redirect response with the registered redirect url in the location header. Supposed to be opened in a browser.",
+    description = "Returns a redirect response with the registered redirect url in the location header. The status code depends on the `redirect_type` of the redirect and defaults to `307 Temporary Redirect`. If `passthrough` is enabled for the redirect, the query string is appended to the query of the redirect url. For `template` redirects the `{query}` placeholder is replaced with the query string, templates with numbered placeholders respond with `404 Not Found`. The `query_params` of the redirect are added to the redirect url last, parameters the url already contains are kept and not overwritten. Secondary aliases resolve to the redirect they belong to. If no alias matches exactly and case-insensitive resolution is enabled for the redirect or the server, the alias is matched regardless of case. Redirects with `preview` enabled respond with a preview page showing the destination, the owner and the creation date instead of redirecting. Appending `+` to the alias, like `/gh+`, shows the preview page for any redirect without counting a click. Redirects with a password show a password form instead, which is submitted via `POST /{alias}`. While it is disabled, outside of the window set by `active_from` and `active_until`, after it expired or reached its click limit, the redirect responds with `307 Temporary Redirect` to its `fallback_url`. Without a `fallback_url`, as well as for unknown aliases, the server wide fallback applies, which either redirects to `VIA_ALIAS_FALLBACK_URL`, responds with the page from `VIA_ALIAS_FALLBACK_PAGE` or responds with the plain status code. Supposed to be opened in a browser.",
     params(
         ("alias" = String, Path, description = "The redirect alias."),
     ),
     security(),
     operation_id="follow_redirect",
     responses(
-        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect doesn't exist."),
+        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect doesn't exist or is not active and has no `fallback_url`."),
+        (status = StatusCode::GONE, description = "Gone. Redirect has expired or reached its click limit."),
+        (status = StatusCode::OK, description = "Ok. Preview page with the destination of the redirect.", content_type = "text/html", body = String),
+        (status = StatusCode::MOVED_PERMANENTLY, description = "Moved Permanently. Follow redirect",
+            headers(
+                ("Location" = String, description = "URL of the created resource")
+            )
+        ),
+        (status = StatusCode::FOUND, description = "Found. Follow redirect",
+            headers(
+                ("Location" = String, description = "URL of the created resource")
+            )
+        ),
         (status = StatusCode::TEMPORARY_REDIRECT, description = "Temporary Redirect. Follow redirect",
             headers(
                 ("Location" = String, description = "URL of the created resource")
             )
+        ),
+        (status = StatusCode::PERMANENT_REDIRECT, description = "Permanent Redirect. Follow redirect",
+            headers(
+                ("Location" = String, description = "URL of the created resource")
+            )
         )
     )
 )]
 pub(crate) async fn follow_redirect_handler(
     State(app_state): State<AppContext>,
     Path(alias): Path<String>,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+) -> Result<Response, DbServiceError> {
+    follow_redirect(&app_state, &alias, None, query.as_deref(), &headers, None).await
+}
+
+#[utoipa::path(get,
+    path = "/{alias}/{path}",
+    tag = "Redirects",
+    summary = "Follow redirect with path",
+    description = "Like `GET /{alias}`, but forwards the remaining path and the query string to the redirect url. Only available for redirects with `passthrough` or `template` enabled, other redirects respond with `404 Not Found`. The path is appended to the path of the redirect url with a single slash in between, the query string is appended to the query of the redirect url and a fragment of the redirect url is kept at the end. For example `/docs/api/v2?x=1` with the redirect url `https://docs.example.com/?lang=en` redirects to `https://docs.example.com/api/v2?lang=en&x=1`. For `template` redirects the placeholders `{1}`, `{2}`, ... are replaced with the path segments, `{path}` with the whole path and `{query}` with the query string. For example `/jira/PROJ-123` with the redirect url `https://jira.example.com/browse/{1}` redirects to `https://jira.example.com/browse/PROJ-123`. If a numbered segment is missing, the redirect responds with `404 Not Found`. Like for `GET /{alias}`, a `+` after the alias shows the preview page. Supposed to be opened in a browser.",
+    params(
+        ("alias" = String, Path, description = "The redirect alias."),
+        ("path" = String, Path, description = "The path that is appended to the redirect url. May contain slashes."),
+    ),
+    security(),
+    operation_id="follow_redirect_with_path",
+    responses(
+        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect doesn't exist, doesn't have `passthrough` or `template` enabled, a path segment of the template is missing or the redirect is not active and has no `fallback_url`."),
+        (status = StatusCode::GONE, description = "Gone. Redirect has expired or reached its click limit."),
+        (status = StatusCode::OK, description = "Ok. Preview page with the destination of the redirect.", content_type = "text/html", body = String),
+        (status = StatusCode::MOVED_PERMANENTLY, description = "Moved Permanently. Follow redirect",
+            headers(
+                ("Location" = String, description = "URL of the created resource")
+            )
+        ),
+        (status = StatusCode::FOUND, description = "Found. Follow redirect",
+            headers(
+                ("Location" = String, description = "URL of the created resource")
+            )
+        ),
+        (status = StatusCode::TEMPORARY_REDIRECT, description = "Temporary Redirect. Follow redirect",
+            headers(
+                ("Location" = String, description = "URL of the created resource")
+            )
+        ),
+        (status = StatusCode::PERMANENT_REDIRECT, description = "Permanent Redirect. Follow redirect",
+            headers(
+                ("Location" = String, description = "URL of the created resource")
+            )
+        )
+    )
+)]
+pub(crate) async fn follow_redirect_with_path_handler(
+    State(app_state): State<AppContext>,
+    Path((alias, _path)): Path<(String, String)>,
+    uri: Uri,
+    headers: HeaderMap,
+) -> Result<Response, DbServiceError> {
+    let path = forwarded_path(&uri);
+    follow_redirect(&app_state, &alias, Some(path), uri.query(), &headers, None).await
+}
+
+#[utoipa::path(post,
+    path = "/{alias}",
+    tag = "Redirects",
+    summary = "Unlock password protected redirect",
+    description = "Submits the password form that `GET /{alias}` shows for redirects with a password. With the correct password the response redirects to the redirect url with `303 See Other`, otherwise the form is shown again. After 5 wrong passwords within 5 minutes, further attempts from the same address respond with `429 Too Many Requests` until the 5 minutes have passed. Supposed to be opened in a browser.",
+    params(
+        ("alias" = String, Path, description = "The redirect alias."),
+    ),
+    request_body(content = RedirectPasswordDTO, content_type = "application/x-www-form-urlencoded"),
+    security(),
+    operation_id="unlock_redirect",
+    responses(
+        (status = StatusCode::SEE_OTHER, description = "See Other. Follow redirect",
+            headers(
+                ("Location" = String, description = "URL of the created resource")
+            )
+        ),
+        (status = StatusCode::OK, description = "Ok. Preview page with the destination of the redirect.", content_type = "text/html", body = String),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. Wrong password, the password form is shown again.", content_type = "text/html", body = String),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too Many Requests. Too many wrong passwords.", content_type = "text/html", body = String),
+        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect doesn't exist or is not active and has no `fallback_url`."),
+        (status = StatusCode::GONE, description = "Gone. Redirect has expired or reached its click limit."),
+    )
+)]
+pub(crate) async fn unlock_redirect_handler(
+    State(app_state): State<AppContext>,
+    Path(alias): Path<String>,
+    RawQuery(query): RawQuery,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Form(form): Form<RedirectPasswordDTO>,
+) -> Result<Response, DbServiceError> {
+    let client = client_address(app_state.app_config.trust_forwarded_for, client, &headers);
+    let password = Some((form.password.as_str(), client.as_str()));
+    follow_redirect(
+        &app_state,
+        &alias,
+        None,
+        query.as_deref(),
+        &headers,
+        password,
+    )
+    .await
+}
+
+#[utoipa::path(post,
+    path = "/{alias}/{path}",
+    tag = "Redirects",
+    summary = "Unlock password protected redirect with path",
+    description = "Like `POST /{alias}`, but forwards the remaining path and the query string to the redirect url like `GET /{alias}/{path}`. Supposed to be opened in a browser.",
+    params(
+        ("alias" = String, Path, description = "The redirect alias."),
+        ("path" = String, Path, description = "The path that is appended to the redirect url. May contain slashes."),
+    ),
+    request_body(content = RedirectPasswordDTO, content_type = "application/x-www-form-urlencoded"),
+    security(),
+    operation_id="unlock_redirect_with_path",
+    responses(
+        (status = StatusCode::SEE_OTHER, description = "See Other. Follow redirect",
+            headers(
+                ("Location" = String, description = "URL of the created resource")
+            )
+        ),
+        (status = StatusCode::OK, description = "Ok. Preview page with the destination of the redirect.", content_type = "text/html", body = String),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. Wrong password, the password form is shown again.", content_type = "text/html", body = String),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too Many Requests. Too many wrong passwords.", content_type = "text/html", body = String),
+        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect doesn't exist, doesn't have `passthrough` or `template` enabled or is not active and has no `fallback_url`."),
+        (status = StatusCode::GONE, description = "Gone. Redirect has expired or reached its click limit."),
+    )
+)]
+pub(crate) async fn unlock_redirect_with_path_handler(
+    State(app_state): State<AppContext>,
+    Path((alias, _path)): Path<(String, String)>,
+    uri: Uri,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Form(form): Form<RedirectPasswordDTO>,
+) -> Result<Response, DbServiceError> {
+    let client = client_address(app_state.app_config.trust_forwarded_for, client, &headers);
+    let password = Some((form.password.as_str(), client.as_str()));
+    let path = forwarded_path(&uri);
+    follow_redirect(
+        &app_state,
+        &alias,
+        Some(path),
+        uri.query(),
+        &headers,
+        password,
+    )
+    .await
+}
+
+// the path is taken from the uri to forward it without decoding
+fn forwarded_path(uri: &Uri) -> &str {
+    uri.path()
+        .trim_start_matches('/')
+        .split_once('/')
+        .map_or("", |(_, path)| path)
+}
+
+async fn follow_redirect(
+    app_state: &AppContext,
+    alias: &str,
+    path: Option<&str>,
+    query: Option<&str>,
+    headers: &HeaderMap,
+    password: Option<(&str, &str)>,
+) -> Result<Response, DbServiceError> {
+    // `+` is not allowed in aliases, so a trailing one always asks for the preview page
+    let (alias, show_preview) = match alias.strip_suffix('+') {
+        Some(alias) => (alias, true),
+        None => (alias, false),
+    };
+    let service = &app_state.redirect_service;
+    let result = match password {
+        Some((password, client)) => {
+            service
+                .unlock_redirect(alias, path, query, password, client, show_preview)
+                .await
+        }
+        None if show_preview => service.preview_redirect(alias, path, query).await,
+        None => service.get_redirect(alias, path, query).await,
+    };
+    // the password is submitted with a POST, which must not be repeated on the target
+    let see_other = |status| {
+        if password.is_some() {
+            StatusCode::SEE_OTHER
+        } else {
+            status
+        }
+    };
+    let redirect = match result {
+        Err(DbServiceError::AuthError(_)) => {
+            return Ok(password_form_response(StatusCode::OK, alias, None));
+        }
+        Err(DbServiceError::InvalidCredentials) => {
+            return Ok(password_form_response(
+                StatusCode::FORBIDDEN,
+                alias,
+                Some("Wrong password."),
+            ));
+        }
+        Err(DbServiceError::TooManyRequests) => {
+            return Ok(password_form_response(
+                StatusCode::TOO_MANY_REQUESTS,
+                alias,
+                Some("Too many wrong passwords. Try again later."),
+            ));
+        }
+        Err(DbServiceError::Inactive(Some(fallback_url))) => {
+            let status = see_other(StatusCode::TEMPORARY_REDIRECT);
+            return Ok((status, [(header::LOCATION, fallback_url)]).into_response());
+        }
+        Err(
+            e @ (DbServiceError::NotFoundError
+            | DbServiceError::Inactive(None)
+            | DbServiceError::Gone(_)),
+        ) => {
+            let status = see_other(StatusCode::TEMPORARY_REDIRECT);
+            return Ok(fallback_response(&app_state.app_config.fallback, e, status));
+        }
+        res => res?,
+    };
+    if show_preview {
+        return preview_response(app_state, &redirect).await;
+    }
+    app_state.click_service.record_click(ClickEvent::new(
+        redirect.id.clone(),
+        header_value(headers, header::REFERER),
+        header_value(headers, header::USER_AGENT),
+    ));
+    if redirect.preview {
+        return preview_response(app_state, &redirect).await;
+    }
+    let status = see_other(redirect_status(redirect.redirect_type));
+    Ok((status, [(header::LOCATION, redirect.url)]).into_response())
+}
+
+async fn preview_response(
+    app_state: &AppContext,
+    redirect: &Redirect,
+) -> Result<Response, DbServiceError> {
+    let owner = app_state
+        .user_service
+        .get_simple_user_info(&redirect.owner)
+        .await?;
+    Ok(Html(render_preview(redirect, &owner.name)).into_response())
+}
+
+#[utoipa::path(get,
+    path = "/api/redirects/{alias}/stats",
+    tag = "Redirects",
+    summary = "Get redirect stats",
+    description = "Returns the click statistics of a redirect, including the clicks of all its secondary aliases. Users can only view stats of redirects they have created. Clicks are recorded asynchronously and may take a few seconds to show up. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    params(
+        ("alias" = String, Path, description = "The redirect alias."),
+    ),
+    security(("bearer_auth" = [])),
+    operation_id="get_redirect_stats",
+    responses(
+        (status = StatusCode::OK, description = "Ok. Returns the click statistics of the redirect.", body = RedirectStatsDTO),
+        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect doesn't exist."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authenticated but doesn't have permission."),
+))]
+async fn get_redirect_stats_handler(
+    State(app_state): State<AppContext>,
+    Path(alias): Path<String>,
+    Extension(user_claims): Extension<UserClaimsDTO>,
+) -> Result<impl IntoResponse, DbServiceError> {
+    let stats = app_state
+        .redirect_service
+        .get_redirect_stats(&alias, &user_claims.user_id)
+        .await?;
+    Ok(Json(stats).into_response())
+}
+
+#[utoipa::path(get,
+    path = "/api/redirects/{alias}/analytics",
+    tag = "Redirects",
+    summary = "Get redirect analytics",
+    description = "Returns the clicks of a redirect and all its secondary aliases aggregated into hourly or daily buckets. Empty buckets are included. Users can only view analytics of redirects they have created. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    params(
+        ("alias" = String, Path, description = "The redirect alias."),
+        AnalyticsQueryDTO,
+    ),
+    security(("bearer_auth" = [])),
+    operation_id="get_redirect_analytics",
+    responses(
+        (status = StatusCode::OK, description = "Ok. Returns the click histogram of the redirect.", body = ClickHistogramDTO),
+        (status = StatusCode::BAD_REQUEST, description = "Bad Request. The requested range is invalid."),
+        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect doesn't exist."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authenticated but doesn't have permission."),
+))]
+async fn get_redirect_analytics_handler(
+    State(app_state): State<AppContext>,
+    Path(alias): Path<String>,
+    Query(query): Query<AnalyticsQueryDTO>,
+    Extension(user_claims): Extension<UserClaimsDTO>,
+) -> Result<impl IntoResponse, DbServiceError> {
+    let histogram = app_state
+        .analytics_service
+        .get_redirect_histogram(&alias, &user_claims.user_id, &query)
+        .await?;
+    Ok(Json(histogram).into_response())
+}
+
+#[utoipa::path(put,
+    path = "/api/redirects/{alias}/tags/{tag}",
+    tag = "Redirects",
+    summary = "Add tag to redirect",
+    description = "Adds a tag to a redirect. Tags are created on first use and can be used to filter `GET /api/redirects`. Users can only tag redirects they have created. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    params(
+        ("alias" = String, Path, description = "The redirect alias."),
+        ("tag" = String, Path, description = "The tag. Allowed characters are a-z, A-Z, 0-9, - and _."),
+    ),
+    security(("bearer_auth" = [])),
+    operation_id="add_redirect_tag",
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "No Content. Tag added to the redirect."),
+        (status = StatusCode::BAD_REQUEST, description = "Bad Request. Tag doesn't match requirements."),
+        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect doesn't exist."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authenticated but doesn't have permission."),
+))]
+async fn add_redirect_tag_handler(
+    State(app_state): State<AppContext>,
+    Path((alias, tag)): Path<(String, String)>,
+    Extension(user_claims): Extension<UserClaimsDTO>,
+) -> Result<Response, DbServiceError> {
+    app_state
+        .tag_service
+        .add_tag(&alias, &tag, &user_claims.user_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[utoipa::path(delete,
+    path = "/api/redirects/{alias}/tags/{tag}",
+    tag = "Redirects",
+    summary = "Remove tag from redirect",
+    description = "Removes a tag from a redirect. Users can only change tags of redirects they have created. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    params(
+        ("alias" = String, Path, description = "The redirect alias."),
+        ("tag" = String, Path, description = "The tag."),
+    ),
+    security(("bearer_auth" = [])),
+    operation_id="remove_redirect_tag",
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "No Content. Tag removed from the redirect."),
+        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect doesn't exist or doesn't have the tag."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authenticated but doesn't have permission."),
+))]
+async fn remove_redirect_tag_handler(
+    State(app_state): State<AppContext>,
+    Path((alias, tag)): Path<(String, String)>,
+    Extension(user_claims): Extension<UserClaimsDTO>,
+) -> Result<Response, DbServiceError> {
+    app_state
+        .tag_service
+        .remove_tag(&alias, &tag, &user_claims.user_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[utoipa::path(put,
+    path = "/api/redirects/{alias}/aliases/{secondary_alias}",
+    tag = "Redirects",
+    summary = "Add alias to redirect",
+    description = "Adds a secondary alias to a redirect. Following a secondary alias behaves exactly like following the redirect itself, so changes to the redirect apply to all of its aliases and clicks are counted for the redirect. Secondary aliases follow the same rules as aliases on creation and share their namespace. A redirect can have up to 20 secondary aliases. Users can only change aliases of redirects they have created. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    params(
+        ("alias" = String, Path, description = "The redirect alias."),
+        ("secondary_alias" = String, Path, description = "The secondary alias. Allowed characters are a-z, A-Z, 0-9, - and _."),
+    ),
+    security(("bearer_auth" = [])),
+    operation_id="add_redirect_alias",
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "No Content. Alias added to the redirect."),
+        (status = StatusCode::BAD_REQUEST, description = "Bad Request. Alias doesn't match requirements or the redirect has too many aliases."),
+        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect doesn't exist."),
+        (status = StatusCode::CONFLICT, description = "Conflict. The alias is already taken."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authenticated but doesn't have permission."),
+))]
+async fn add_redirect_alias_handler(
+    State(app_state): State<AppContext>,
+    Path((alias, secondary_alias)): Path<(String, String)>,
+    Extension(user_claims): Extension<UserClaimsDTO>,
+) -> Result<Response, DbServiceError> {
+    app_state
+        .redirect_service
+        .add_secondary_alias(&alias, &secondary_alias, &user_claims.user_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[utoipa::path(delete,
+    path = "/api/redirects/{alias}/aliases/{secondary_alias}",
+    tag = "Redirects",
+    summary = "Remove alias from redirect",
+    description = "Removes a secondary alias from a redirect. Users can only change aliases of redirects they have created. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    params(
+        ("alias" = String, Path, description = "The redirect alias."),
+        ("secondary_alias" = String, Path, description = "The secondary alias."),
+    ),
+    security(("bearer_auth" = [])),
+    operation_id="remove_redirect_alias",
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "No Content. Alias removed from the redirect."),
+        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect doesn't exist or doesn't have the alias."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authenticated but doesn't have permission."),
+))]
+async fn remove_redirect_alias_handler(
+    State(app_state): State<AppContext>,
+    Path((alias, secondary_alias)): Path<(String, String)>,
+    Extension(user_claims): Extension<UserClaimsDTO>,
+) -> Result<Response, DbServiceError> {
+    app_state
+        .redirect_service
+        .remove_secondary_alias(&alias, &secondary_alias, &user_claims.user_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[utoipa::path(get,
+    path = "/api/redirects/{alias}/history",
+    tag = "Redirects",
+    summary = "Get url history of redirect",
+    description = "Returns every change of the url of a redirect with the previous and the new url, who changed it and when, newest first. Users can only see the history of redirects they have created. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    params(
+        ("alias" = String, Path, description = "The redirect alias."),
+    ),
+    security(("bearer_auth" = [])),
+    operation_id="get_redirect_history",
+    responses(
+        (status = StatusCode::OK, description = "Ok. Returns the url history of the redirect.", body = RedirectHistoryDTO),
+        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect doesn't exist."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authenticated but doesn't have permission."),
+))]
+async fn get_redirect_history_handler(
+    State(app_state): State<AppContext>,
+    Path(alias): Path<String>,
+    Extension(user_claims): Extension<UserClaimsDTO>,
 ) -> Result<impl IntoResponse, DbServiceError> {
-    let redirect = app_state.redirect_service.get_redirect(&alias).await?;
-    Ok(Redirect::temporary(&redirect.url).into_response())
+    let history = app_state
+        .redirect_service
+        .get_redirect_history(&alias, &user_claims.user_id)
+        .await?;
+    Ok((StatusCode::OK, Json(history)).into_response())
+}
+
+#[utoipa::path(post,
+    path = "/api/redirects/{alias}/history/{id}/rollback",
+    tag = "Redirects",
+    summary = "Roll back url of redirect",
+    description = "Restores the url a redirect had before the given change of its history. The rollback itself is recorded as a new change, so it can be undone the same way. Users can only roll back redirects they have created. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    params(
+        ("alias" = String, Path, description = "The redirect alias."),
+        ("id" = i64, Path, description = "The id of the history entry."),
+    ),
+    security(("bearer_auth" = [])),
+    operation_id="rollback_redirect",
+    responses(
+        (status = StatusCode::OK, description = "Ok. Returns the updated redirect.", body = RedirectDTO),
+        (status = StatusCode::BAD_REQUEST, description = "Bad Request. The previous url doesn't match the current `template` or `passthrough` settings."),
+        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect or history entry doesn't exist."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authenticated but doesn't have permission."),
+))]
+async fn rollback_redirect_handler(
+    State(app_state): State<AppContext>,
+    Path((alias, id)): Path<(String, i64)>,
+    Extension(user_claims): Extension<UserClaimsDTO>,
+) -> Result<impl IntoResponse, DbServiceError> {
+    let updated = app_state
+        .redirect_service
+        .rollback_redirect(&alias, id, &user_claims.user_id)
+        .await?;
+    Ok((StatusCode::OK, Json(updated)).into_response())
+}
+
+#[utoipa::path(post,
+    path = "/api/redirects/{alias}/enable",
+    tag = "Redirects",
+    summary = "Enable redirect",
+    description = "Enables a disabled redirect, so it can be followed again. Redirects disabled by an admin can't be enabled by their owner. Users can only enable redirects they have created. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    params(
+        ("alias" = String, Path, description = "The redirect alias."),
+    ),
+    security(("bearer_auth" = [])),
+    operation_id="enable_redirect",
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "No Content. Redirect enabled."),
+        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect doesn't exist."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authenticated but doesn't have permission or the redirect was disabled by an admin."),
+))]
+async fn enable_redirect_handler(
+    State(app_state): State<AppContext>,
+    Path(alias): Path<String>,
+    Extension(user_claims): Extension<UserClaimsDTO>,
+) -> Result<Response, DbServiceError> {
+    app_state
+        .redirect_service
+        .set_redirect_enabled(&alias, true, &user_claims.user_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[utoipa::path(post,
+    path = "/api/redirects/{alias}/disable",
+    tag = "Redirects",
+    summary = "Disable redirect",
+    description = "Disables a redirect without deleting it. Following a disabled redirect behaves like an unknown alias, unless the redirect has a `fallback_url`. Users can only disable redirects they have created. Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
+    params(
+        ("alias" = String, Path, description = "The redirect alias."),
+    ),
+    security(("bearer_auth" = [])),
+    operation_id="disable_redirect",
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "No Content. Redirect disabled."),
+        (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect doesn't exist."),
+        (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
+        (status = StatusCode::FORBIDDEN, description = "Forbidden. User authenticated but doesn't have permission or the redirect was disabled by an admin."),
+))]
+async fn disable_redirect_handler(
+    State(app_state): State<AppContext>,
+    Path(alias): Path<String>,
+    Extension(user_claims): Extension<UserClaimsDTO>,
+) -> Result<Response, DbServiceError> {
+    app_state
+        .redirect_service
+        .set_redirect_enabled(&alias, false, &user_claims.user_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
 }
 
 #[utoipa::path(patch,
     path = "/api/redirects/{alias}",
     tag = "Redirects",
     summary = "Update redirect",
-    description = "Updates the registered url of a redirect. Changing the alias requires deleting the redirect and recreating it with the desired alias.
+    description = "Updates a redirect. All fields are optional, only the supplied ones are changed. A new `alias` has to follow the same rules as on creation. Passing another user's id as `owner` hands the redirect over to that user. Changes apply to all secondary aliases of the redirect. Passing `null` as `active_from`, `active_until`, `fallback_url` or `password` removes it, passing `null` as `case_insensitive` resets it to the server default.
     Requires authentication. Pass a JWT as a bearer token in the `Authorization` header.",
     params(
         ("alias" = String, Path, description = "The redirect alias."),
@@ -144,7 +1051,9 @@ pub(crate) async fn follow_redirect_handler(
     security(("bearer_auth" = [])),
     operation_id="update_redirect",
     responses(
-        (status = StatusCode::OK, description = "Ok. Changed the url of the redirect.", body = RedirectDTO),
+        (status = StatusCode::OK, description = "Ok. Returns the updated redirect.", body = RedirectDTO),
+        (status = StatusCode::BAD_REQUEST, description = "Bad Request. Alias or url don't match requirements or the new owner doesn't exist."),
+        (status = StatusCode::CONFLICT, description = "A redirect with the new alias already exists."),
         (status = StatusCode::NOT_FOUND, description = "Not Found. Redirect doesn't exist."),
         (status = StatusCode::UNAUTHORIZED, description = "Unauthorized. No valid access token."),
         (status = StatusCode::FORBIDDEN, description = "Forbidden. User authenticated but doesn't have permission."),
@@ -153,18 +1062,72 @@ async fn update_redirect_handler(
     State(app_state): State<AppContext>,
     Path(alias): Path<String>,
     Extension(user_claims): Extension<UserClaimsDTO>,
-    Json(payload): Json<UpdateUrlDTO>,
+    Json(payload): Json<UpdateRedirectDTO>,
 ) -> Result<impl IntoResponse, DbServiceError> {
-    app_state
+    let updated = app_state
         .redirect_service
         .update_redirect(&alias, &payload, &user_claims.user_id)
         .await?;
-    Ok((
-        StatusCode::OK,
-        Json(RedirectDTO {
-            alias,
-            url: payload.url,
-        }),
-    )
-        .into_response())
+    Ok((StatusCode::OK, Json(updated)).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn fallback_response_handles_unknown_alias() {
+        let status = fallback_response(
+            &Fallback::Status,
+            DbServiceError::NotFoundError,
+            StatusCode::TEMPORARY_REDIRECT,
+        );
+        assert_eq!(status.status(), StatusCode::NOT_FOUND);
+
+        let redirect = fallback_response(
+            &Fallback::Redirect("https://example.com".to_owned()),
+            DbServiceError::NotFoundError,
+            StatusCode::SEE_OTHER,
+        );
+        assert_eq!(redirect.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            redirect.headers().get(header::LOCATION).unwrap(),
+            "https://example.com"
+        );
+
+        let page = fallback_response(
+            &Fallback::Page(Arc::from("<h1>Not here</h1>")),
+            DbServiceError::NotFoundError,
+            StatusCode::TEMPORARY_REDIRECT,
+        );
+        assert_eq!(page.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            page.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/html; charset=utf-8"
+        );
+    }
+
+    #[test]
+    fn format_date_converts_unix_timestamps() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(86_399), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        // 2100 is not a leap year
+        assert_eq!(format_date(4_107_456_000), "2100-02-28");
+        assert_eq!(format_date(4_107_542_400), "2100-03-01");
+        assert_eq!(format_date(-1), "1969-12-31");
+        assert_eq!(format_date(-2_203_891_200), "1900-03-01");
+    }
+
+    #[test]
+    fn client_address_uses_last_forwarded_entry_when_trusted() {
+        let socket: SocketAddr = "10.0.0.1:4321".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "1.2.3.4, 203.0.113.7".parse().unwrap());
+        assert_eq!(client_address(true, socket, &headers), "203.0.113.7");
+        assert_eq!(client_address(false, socket, &headers), "10.0.0.1");
+        assert_eq!(client_address(true, socket, &HeaderMap::new()), "10.0.0.1");
+    }
 }
@@ -1,13 +1,21 @@
 use async_trait::async_trait;
 
 use crate::{
-    model::{Redirect, UpdateUrlDTO, User, UserRegistrationToken},
+    model::{
+        AliasCollision, ClickBucketDTO, ClickEvent, Redirect, RedirectAlias,
+        RedirectHistoryEntryDTO, RedirectPage, RedirectPageQuery, RedirectTag, TrashedUserDTO,
+        UpdateRedirectDTO, User, UserRegistrationToken,
+    },
     service::DbServiceError,
 };
+mod click_repo;
 mod redirect_repo;
+mod tag_repo;
 mod user_registration_token_repo;
 mod user_repo;
+pub use crate::data::click_repo::ClickRepoSqliteImpl;
 pub use crate::data::redirect_repo::RedirectRepoSqliteImpl;
+pub use crate::data::tag_repo::TagRepoSqliteImpl;
 pub(crate) use crate::data::user_registration_token_repo::UserRegistrationTokenInMemoryImpl;
 pub use crate::data::user_repo::UserRepoSqliteImpl;
 pub(crate) struct DeletedResources {
@@ -26,15 +34,67 @@ impl From<sqlx::Error> for UserRepoError {
     }
 }
 
+pub type RedirectValidator = dyn Fn(&Redirect) -> Result<(), DbServiceError> + Send + Sync;
+
+#[derive(Debug)]
+pub enum RedirectUpdateError {
+    UnknownOwner,
+    Invalid(DbServiceError),
+    Db(sqlx::Error),
+}
+
+impl From<sqlx::Error> for RedirectUpdateError {
+    fn from(e: sqlx::Error) -> Self {
+        Self::Db(e)
+    }
+}
+
 #[async_trait]
 pub trait RedirectRepo: Send + Sync + 'static {
     async fn read_redirect_by_alias(&self, alias: &str) -> Result<Redirect, sqlx::Error>;
+    async fn read_deleted_redirect_by_alias(&self, alias: &str) -> Result<Redirect, sqlx::Error>;
+    async fn read_redirect_by_alias_key(&self, alias: &str) -> Result<Redirect, sqlx::Error>;
+    async fn read_redirect_by_secondary_alias(&self, alias: &str) -> Result<Redirect, sqlx::Error>;
+    async fn read_secondary_aliases_by_redirect_ids(
+        &self,
+        redirect_ids: &[String],
+    ) -> Result<Vec<RedirectAlias>, sqlx::Error>;
+    async fn create_secondary_alias(
+        &self,
+        redirect_id: &str,
+        alias: &str,
+        created_at: i64,
+    ) -> Result<(), sqlx::Error>;
+    async fn delete_secondary_alias(
+        &self,
+        redirect_id: &str,
+        alias: &str,
+    ) -> Result<u64, sqlx::Error>;
+    async fn read_redirect_history(
+        &self,
+        redirect_id: &str,
+    ) -> Result<Vec<RedirectHistoryEntryDTO>, sqlx::Error>;
+    async fn read_redirect_history_entry(
+        &self,
+        redirect_id: &str,
+        entry_id: i64,
+    ) -> Result<RedirectHistoryEntryDTO, sqlx::Error>;
+    async fn resolve_alias_collisions(&self) -> Result<Vec<AliasCollision>, sqlx::Error>;
     async fn create_redirect(&self, redirect: &Redirect) -> Result<(), sqlx::Error>;
-    async fn read_all_redirects(&self) -> Result<Vec<Redirect>, sqlx::Error>;
+    async fn create_redirects(
+        &self,
+        redirects: &[Redirect],
+        dry_run: bool,
+    ) -> Result<Vec<bool>, sqlx::Error>;
+    async fn read_all_redirects(
+        &self,
+        page: &RedirectPageQuery,
+    ) -> Result<RedirectPage, sqlx::Error>;
     async fn read_all_redirects_by_user_id(
         &self,
         user_id: &str,
-    ) -> Result<Vec<Redirect>, sqlx::Error>;
+        page: &RedirectPageQuery,
+    ) -> Result<RedirectPage, sqlx::Error>;
     #[allow(unused)]
     async fn delete_redirect_by_alias(&self, alias: &str) -> Result<u64, sqlx::Error>;
     async fn delete_redirect_by_id(&self, id: &str) -> Result<u64, sqlx::Error>;
@@ -43,12 +103,59 @@ pub trait RedirectRepo: Send + Sync + 'static {
         alias: &str,
         user_id: &str,
     ) -> Result<u64, sqlx::Error>;
+    async fn restore_redirect_by_id(&self, id: &str) -> Result<u64, sqlx::Error>;
     async fn update_redirect_by_alias(
         &self,
         alias: &str,
-        redirect: &UpdateUrlDTO,
+        redirect: &UpdateRedirectDTO,
+        pwhash: Option<Option<&str>>,
         user_id: &str,
+        validate: &RedirectValidator,
+    ) -> Result<u64, RedirectUpdateError>;
+    async fn update_redirect_url_by_id(
+        &self,
+        id: &str,
+        url: &str,
+        user_id: &str,
+    ) -> Result<u64, sqlx::Error>;
+    async fn update_redirect_enabled_by_id(
+        &self,
+        id: &str,
+        enabled: bool,
+        locked: bool,
+        user_id: &str,
+    ) -> Result<u64, sqlx::Error>;
+    async fn consume_redirect_click(&self, id: &str) -> Result<u64, sqlx::Error>;
+}
+
+#[async_trait]
+pub(crate) trait ClickRepo: Send + Sync + 'static {
+    async fn create_click_events(&self, events: &[ClickEvent]) -> Result<u64, sqlx::Error>;
+    async fn read_last_click_by_redirect_id(
+        &self,
+        redirect_id: &str,
+    ) -> Result<Option<i64>, sqlx::Error>;
+    async fn read_click_buckets(
+        &self,
+        redirect_id: Option<&str>,
+        from: i64,
+        to: i64,
+        bucket_size: i64,
+    ) -> Result<Vec<ClickBucketDTO>, sqlx::Error>;
+}
+
+#[async_trait]
+pub(crate) trait TagRepo: Send + Sync + 'static {
+    async fn add_tag_to_redirect(&self, redirect_id: &str, tag: &str) -> Result<u64, sqlx::Error>;
+    async fn remove_tag_from_redirect(
+        &self,
+        redirect_id: &str,
+        tag: &str,
     ) -> Result<u64, sqlx::Error>;
+    async fn read_tags_by_redirect_ids(
+        &self,
+        redirect_ids: &[String],
+    ) -> Result<Vec<RedirectTag>, sqlx::Error>;
 }
 
 #[async_trait]
@@ -60,6 +167,8 @@ pub trait UserRepo: Send + Sync + 'static {
     async fn count_user_with_is_admin(&self) -> Result<i64, sqlx::Error>;
     async fn update_user_by_id(&self, user: &User) -> Result<u64, sqlx::Error>;
     async fn delete_user_by_id(&self, user: &str) -> Result<DeletedResources, UserRepoError>;
+    async fn read_deleted_users(&self) -> Result<Vec<TrashedUserDTO>, sqlx::Error>;
+    async fn restore_user_by_id(&self, user_id: &str) -> Result<u64, sqlx::Error>;
 }
 
 #[async_trait]
@@ -0,0 +1,301 @@
+use async_trait::async_trait;
+use sqlx::{Pool, Sqlite};
+
+use crate::{
+    data::ClickRepo,
+    model::{ClickBucketDTO, ClickEvent},
+};
+
+pub struct ClickRepoSqliteImpl {
+    db: Pool<Sqlite>,
+}
+
+impl ClickRepoSqliteImpl {
+    pub fn new(db: Pool<Sqlite>) -> Self {
+        ClickRepoSqliteImpl { db }
+    }
+}
+
+#[async_trait]
+impl ClickRepo for ClickRepoSqliteImpl {
+    async fn create_click_events(&self, events: &[ClickEvent]) -> Result<u64, sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+        let mut recorded = 0;
+        for event in events {
+            // redirects can be deleted between the click and the flush, those events are dropped
+            let inserted = sqlx::query(
+                "INSERT INTO click_events (redirect_id, clicked_at, referrer, user_agent)
+                SELECT $1, $2, $3, $4 WHERE EXISTS (SELECT 1 FROM redirects WHERE id = $1);",
+            )
+            .bind(&event.redirect_id)
+            .bind(event.clicked_at)
+            .bind(&event.referrer)
+            .bind(&event.user_agent)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+            if inserted > 0 {
+                sqlx::query("UPDATE redirects SET clicks = clicks + 1 WHERE id = $1;")
+                    .bind(&event.redirect_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            recorded += inserted;
+        }
+        tx.commit().await?;
+        Ok(recorded)
+    }
+
+    async fn read_last_click_by_redirect_id(
+        &self,
+        redirect_id: &str,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        sqlx::query_scalar("SELECT MAX(clicked_at) FROM click_events WHERE redirect_id = $1;")
+            .bind(redirect_id)
+            .fetch_one(&self.db)
+            .await
+    }
+
+    async fn read_click_buckets(
+        &self,
+        redirect_id: Option<&str>,
+        from: i64,
+        to: i64,
+        bucket_size: i64,
+    ) -> Result<Vec<ClickBucketDTO>, sqlx::Error> {
+        sqlx::query_as::<_, ClickBucketDTO>(
+            "SELECT (clicked_at / $1) * $1 AS bucket_start, COUNT(*) AS clicks FROM click_events
+            WHERE clicked_at >= $2 AND clicked_at < $3 AND ($4 IS NULL OR redirect_id = $4)
+            GROUP BY bucket_start ORDER BY bucket_start;",
+        )
+        .bind(bucket_size)
+        .bind(from)
+        .bind(to)
+        .bind(redirect_id)
+        .fetch_all(&self.db)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::SqlitePool;
+    use uuid::Uuid;
+
+    use crate::{
+        data::{ClickRepo, ClickRepoSqliteImpl},
+        model::{ClickBucketDTO, ClickEvent, Redirect, User},
+    };
+
+    async fn setup_test_db() -> SqlitePool {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        pool
+    }
+
+    async fn seed_test_db(pool: &SqlitePool) -> Redirect {
+        let owner = User {
+            id: "some_id_string".to_owned(),
+            is_admin: false,
+            name: "testuser".to_owned(),
+            pwhash: "not_a_pw_hash".to_owned(),
+        };
+        sqlx::query("INSERT INTO users (id, name, pwhash, is_admin) VALUES ($1, $2, $3, $4);")
+            .bind(&owner.id)
+            .bind(&owner.name)
+            .bind(&owner.pwhash)
+            .bind(owner.is_admin)
+            .execute(pool)
+            .await
+            .unwrap();
+
+        let redirect = Redirect {
+            id: Uuid::new_v4().to_string(),
+            alias: "somealias".to_string(),
+            url: "https://someurl.com".to_string(),
+            owner: owner.id,
+            ..Default::default()
+        };
+        sqlx::query("INSERT INTO redirects (id, alias, url, owner) VALUES ($1, $2, $3, $4);")
+            .bind(&redirect.id)
+            .bind(&redirect.alias)
+            .bind(&redirect.url)
+            .bind(&redirect.owner)
+            .execute(pool)
+            .await
+            .unwrap();
+        redirect
+    }
+
+    fn click_event(redirect_id: &str, clicked_at: i64) -> ClickEvent {
+        ClickEvent {
+            redirect_id: redirect_id.to_owned(),
+            clicked_at,
+            referrer: Some("https://referrer.com".to_owned()),
+            user_agent: None,
+        }
+    }
+
+    async fn read_clicks_counter(redirect_id: &str, pool: &SqlitePool) -> i64 {
+        sqlx::query_scalar("SELECT clicks FROM redirects WHERE id = $1;")
+            .bind(redirect_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn read_events_from_test_db(pool: &SqlitePool) -> Vec<ClickEvent> {
+        sqlx::query_as::<_, ClickEvent>(
+            "SELECT redirect_id, clicked_at, referrer, user_agent FROM click_events;",
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_create_click_events_success() {
+        let pool = setup_test_db().await;
+        let repo = ClickRepoSqliteImpl::new(pool.clone());
+        let redirect = seed_test_db(&pool).await;
+        let events = vec![
+            click_event(&redirect.id, 100),
+            click_event(&redirect.id, 200),
+        ];
+
+        let result = repo.create_click_events(&events).await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), 2);
+        assert_eq!(read_clicks_counter(&redirect.id, &pool).await, 2);
+        assert_eq!(read_events_from_test_db(&pool).await, events);
+    }
+
+    #[tokio::test]
+    async fn test_create_click_events_for_unknown_redirect_are_dropped() {
+        let pool = setup_test_db().await;
+        let repo = ClickRepoSqliteImpl::new(pool.clone());
+        let redirect = seed_test_db(&pool).await;
+        let events = vec![
+            click_event("unknown_redirect_id", 100),
+            click_event(&redirect.id, 200),
+        ];
+
+        let result = repo.create_click_events(&events).await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), 1);
+        assert_eq!(read_clicks_counter(&redirect.id, &pool).await, 1);
+        assert_eq!(
+            read_events_from_test_db(&pool).await,
+            vec![events[1].clone()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_last_click_returns_latest_timestamp() {
+        let pool = setup_test_db().await;
+        let repo = ClickRepoSqliteImpl::new(pool.clone());
+        let redirect = seed_test_db(&pool).await;
+        repo.create_click_events(&[
+            click_event(&redirect.id, 300),
+            click_event(&redirect.id, 100),
+        ])
+        .await
+        .unwrap();
+
+        let result = repo.read_last_click_by_redirect_id(&redirect.id).await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), Some(300));
+    }
+
+    #[tokio::test]
+    async fn test_read_last_click_without_clicks_returns_none() {
+        let pool = setup_test_db().await;
+        let repo = ClickRepoSqliteImpl::new(pool.clone());
+        let redirect = seed_test_db(&pool).await;
+
+        let result = repo.read_last_click_by_redirect_id(&redirect.id).await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_read_click_buckets_groups_by_bucket_size() {
+        let pool = setup_test_db().await;
+        let repo = ClickRepoSqliteImpl::new(pool.clone());
+        let redirect = seed_test_db(&pool).await;
+        repo.create_click_events(&[
+            click_event(&redirect.id, 3600),
+            click_event(&redirect.id, 3700),
+            click_event(&redirect.id, 7300),
+            click_event(&redirect.id, 20000),
+        ])
+        .await
+        .unwrap();
+
+        let result = repo
+            .read_click_buckets(Some(&redirect.id), 0, 10800, 3600)
+            .await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap(),
+            vec![
+                ClickBucketDTO {
+                    bucket_start: 3600,
+                    clicks: 2
+                },
+                ClickBucketDTO {
+                    bucket_start: 7200,
+                    clicks: 1
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_click_buckets_without_redirect_id_counts_all_redirects() {
+        let pool = setup_test_db().await;
+        let repo = ClickRepoSqliteImpl::new(pool.clone());
+        let redirect = seed_test_db(&pool).await;
+        let other_redirect = Redirect {
+            id: Uuid::new_v4().to_string(),
+            alias: "otheralias".to_string(),
+            url: "https://otherurl.com".to_string(),
+            owner: redirect.owner.clone(),
+            ..Default::default()
+        };
+        sqlx::query("INSERT INTO redirects (id, alias, url, owner) VALUES ($1, $2, $3, $4);")
+            .bind(&other_redirect.id)
+            .bind(&other_redirect.alias)
+            .bind(&other_redirect.url)
+            .bind(&other_redirect.owner)
+            .execute(&pool)
+            .await
+            .unwrap();
+        repo.create_click_events(&[
+            click_event(&redirect.id, 100),
+            click_event(&other_redirect.id, 200),
+        ])
+        .await
+        .unwrap();
+
+        let scoped = repo
+            .read_click_buckets(Some(&redirect.id), 0, 86400, 86400)
+            .await
+            .unwrap();
+        let global = repo
+            .read_click_buckets(None, 0, 86400, 86400)
+            .await
+            .unwrap();
+
+        assert_eq!(scoped.len(), 1);
+        assert_eq!(scoped[0].clicks, 1);
+        assert_eq!(global.len(), 1);
+        assert_eq!(global[0].clicks, 2);
+    }
+}
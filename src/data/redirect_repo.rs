@@ -1,77 +1,472 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
-use sqlx::{Pool, Sqlite};
+use sqlx::{Executor, FromRow, Pool, QueryBuilder, Row, Sqlite, types::Json};
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
 
 use crate::{
-    data::RedirectRepo,
-    model::{Redirect, UpdateUrlDTO},
+    data::{RedirectRepo, RedirectUpdateError, RedirectValidator},
+    model::{
+        AliasCollision, CursorValue, Redirect, RedirectAlias, RedirectCursor,
+        RedirectHistoryEntryDTO, RedirectPage, RedirectPageQuery, RedirectSortKey, SortOrder,
+        UpdateRedirectDTO,
+    },
+    now_unix,
 };
 
+const REDIRECT_COLUMNS: &str = "id, alias, url, owner, clicks, expires_at, max_clicks, \
+    remaining_clicks, redirect_type, passthrough, template, query_params, case_insensitive, preview, pwhash, active_from, active_until, fallback_url, enabled, locked, created_at, updated_at, updated_by, deleted_at";
+
+async fn insert_redirect<'e, E>(
+    executor: E,
+    redirect: &Redirect,
+    conflict_clause: &str,
+) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let result = sqlx::query(&format!(
+        "INSERT {conflict_clause} INTO redirects
+        (id, alias, alias_key, url, owner, expires_at, max_clicks, remaining_clicks, redirect_type,
+        passthrough, template, query_params, case_insensitive, preview, pwhash, active_from,
+        active_until, fallback_url, enabled, locked, created_at, updated_at, updated_by)
+        VALUES ($1, $2, lower($2), $3, $4, $5, $6, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15,
+        $16, $17, $18, $19, $20, $21);"
+    ))
+    .bind(&redirect.id)
+    .bind(&redirect.alias)
+    .bind(&redirect.url)
+    .bind(&redirect.owner)
+    .bind(redirect.expires_at)
+    .bind(redirect.max_clicks)
+    .bind(u16::from(redirect.redirect_type))
+    .bind(redirect.passthrough)
+    .bind(redirect.template)
+    .bind(Json(&redirect.query_params))
+    .bind(redirect.case_insensitive)
+    .bind(redirect.preview)
+    .bind(&redirect.pwhash)
+    .bind(redirect.active_from)
+    .bind(redirect.active_until)
+    .bind(&redirect.fallback_url)
+    .bind(redirect.enabled)
+    .bind(redirect.locked)
+    .bind(redirect.created_at)
+    .bind(redirect.updated_at)
+    .bind(&redirect.updated_by)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected())
+}
+
 pub struct RedirectRepoSqliteImpl {
     db: Pool<Sqlite>,
+    cancel_token: Option<CancellationToken>,
+}
+
+impl Drop for RedirectRepoSqliteImpl {
+    fn drop(&mut self) {
+        if let Some(token) = &self.cancel_token {
+            token.cancel();
+        }
+    }
 }
 
 impl RedirectRepoSqliteImpl {
     pub fn new(db: Pool<Sqlite>) -> Self {
-        RedirectRepoSqliteImpl { db }
+        RedirectRepoSqliteImpl {
+            db,
+            cancel_token: None,
+        }
+    }
+
+    pub fn with_cleanup(db: Pool<Sqlite>, interval: Duration, trash_retention: Duration) -> Self {
+        let mut repo = Self::new(db);
+        let cancel_token = CancellationToken::new();
+        tokio::spawn(Self::cleanup_task(
+            repo.db.clone(),
+            cancel_token.clone(),
+            interval,
+            trash_retention,
+        ));
+        repo.cancel_token = Some(cancel_token);
+        repo
+    }
+
+    async fn cleanup_task(
+        db: Pool<Sqlite>,
+        cancel_token: CancellationToken,
+        interval: Duration,
+        trash_retention: Duration,
+    ) {
+        let start = Instant::now() + interval;
+        let mut timer = tokio::time::interval_at(start, interval);
+        timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = timer.tick() => {
+                    let now = now_unix();
+                    let retention = i64::try_from(trash_retention.as_secs()).unwrap_or(i64::MAX);
+                    let purge_before = now.saturating_sub(retention);
+                    // expired redirects answer 410 Gone for the retention period before they are
+                    // moved to the trash and purged like deleted ones
+                    if let Err(e) = Self::trash_expired_redirects_inner(&db, purge_before, now).await {
+                        eprintln!("Failed to trash expired redirects: {e}");
+                    }
+                    if let Err(e) = Self::purge_deleted_redirects_inner(&db, purge_before).await {
+                        eprintln!("Failed to purge deleted redirects: {e}");
+                    }
+                }
+                () = cancel_token.cancelled() => {
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn read_redirects_page(
+        &self,
+        owner: Option<&str>,
+        page: &RedirectPageQuery,
+    ) -> Result<RedirectPage, sqlx::Error> {
+        // the sort column is only ever taken from this match, never from user input
+        let column = match page.sort {
+            RedirectSortKey::Alias => "alias",
+            RedirectSortKey::Created => "created_at",
+            RedirectSortKey::Clicks => "clicks",
+        };
+        let (comparison, direction) = match page.order {
+            SortOrder::Asc => (">", "ASC"),
+            SortOrder::Desc => ("<", "DESC"),
+        };
+
+        let mut query = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {REDIRECT_COLUMNS}, {column} AS sort_value FROM redirects WHERE 1 = 1"
+        ));
+        if page.deleted {
+            query.push(" AND deleted_at IS NOT NULL");
+        } else {
+            query.push(" AND deleted_at IS NULL");
+        }
+        if let Some(owner) = owner {
+            query.push(" AND owner = ").push_bind(owner.to_owned());
+        }
+        if let Some(search) = &page.search {
+            let escaped = search
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            let pattern = format!("%{escaped}%");
+            query
+                .push(" AND (alias LIKE ")
+                .push_bind(pattern.clone())
+                .push(" ESCAPE '\\' OR url LIKE ")
+                .push_bind(pattern)
+                .push(" ESCAPE '\\')");
+        }
+        if let Some(tag) = &page.tag {
+            query
+                .push(
+                    " AND id IN (SELECT rt.redirect_id FROM redirect_tags rt
+                    JOIN tags t ON t.id = rt.tag_id WHERE t.name = ",
+                )
+                .push_bind(tag.clone())
+                .push(")");
+        }
+        if let Some(after) = &page.after {
+            query.push(format!(" AND ({column}, id) {comparison} ("));
+            match &after.value {
+                CursorValue::Text(value) => query.push_bind(value.clone()),
+                CursorValue::Integer(value) => query.push_bind(*value),
+            };
+            query.push(", ").push_bind(after.id.clone()).push(")");
+        }
+        query
+            .push(format!(
+                " ORDER BY {column} {direction}, id {direction} LIMIT "
+            ))
+            .push_bind(page.limit + 1);
+
+        let mut rows = query.build().fetch_all(&self.db).await?;
+        let has_more = rows.len() > usize::try_from(page.limit).unwrap_or_default();
+        rows.truncate(usize::try_from(page.limit).unwrap_or_default());
+
+        let next_cursor = match rows.last() {
+            Some(row) if has_more => Some(RedirectCursor {
+                value: match page.sort {
+                    RedirectSortKey::Alias => CursorValue::Text(row.try_get("sort_value")?),
+                    _ => CursorValue::Integer(row.try_get("sort_value")?),
+                },
+                id: row.try_get("id")?,
+            }),
+            _ => None,
+        };
+        let redirects = rows
+            .iter()
+            .map(Redirect::from_row)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(RedirectPage {
+            redirects,
+            next_cursor,
+        })
+    }
+
+    #[cfg(test)]
+    async fn trash_expired_redirects(
+        &self,
+        expired_before: i64,
+        now: i64,
+    ) -> Result<u64, sqlx::Error> {
+        Self::trash_expired_redirects_inner(&self.db, expired_before, now).await
+    }
+
+    async fn trash_expired_redirects_inner(
+        db: &Pool<Sqlite>,
+        expired_before: i64,
+        now: i64,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE redirects SET deleted_at = $2 WHERE expires_at <= $1 AND deleted_at IS NULL;",
+        )
+        .bind(expired_before)
+        .bind(now)
+        .execute(db)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    #[cfg(test)]
+    async fn purge_deleted_redirects(&self, before: i64) -> Result<u64, sqlx::Error> {
+        Self::purge_deleted_redirects_inner(&self.db, before).await
+    }
+
+    async fn purge_deleted_redirects_inner(
+        db: &Pool<Sqlite>,
+        before: i64,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM redirects WHERE deleted_at <= $1;")
+            .bind(before)
+            .execute(db)
+            .await?;
+        Ok(result.rows_affected())
     }
 }
 
 #[async_trait]
 impl RedirectRepo for RedirectRepoSqliteImpl {
     async fn read_redirect_by_alias(&self, alias: &str) -> Result<Redirect, sqlx::Error> {
-        sqlx::query_as::<_, Redirect>(
-            "SELECT id, alias, url, owner FROM redirects WHERE alias = $1;",
+        sqlx::query_as::<_, Redirect>(&format!(
+            "SELECT {REDIRECT_COLUMNS} FROM redirects WHERE alias = $1 AND deleted_at IS NULL;"
+        ))
+        .bind(alias)
+        .fetch_one(&self.db)
+        .await
+    }
+
+    async fn read_deleted_redirect_by_alias(&self, alias: &str) -> Result<Redirect, sqlx::Error> {
+        sqlx::query_as::<_, Redirect>(&format!(
+            "SELECT {REDIRECT_COLUMNS} FROM redirects WHERE alias = $1 AND deleted_at IS NOT NULL;"
+        ))
+        .bind(alias)
+        .fetch_one(&self.db)
+        .await
+    }
+
+    async fn read_redirect_by_alias_key(&self, alias: &str) -> Result<Redirect, sqlx::Error> {
+        sqlx::query_as::<_, Redirect>(&format!(
+            "SELECT {REDIRECT_COLUMNS} FROM redirects WHERE (alias = $1 OR alias_key = lower($1)
+            OR id = (SELECT redirect_id FROM redirect_aliases WHERE alias_key = lower($1)))
+            AND deleted_at IS NULL ORDER BY alias = $1 DESC LIMIT 1;"
+        ))
+        .bind(alias)
+        .fetch_one(&self.db)
+        .await
+    }
+
+    async fn read_redirect_by_secondary_alias(&self, alias: &str) -> Result<Redirect, sqlx::Error> {
+        sqlx::query_as::<_, Redirect>(&format!(
+            "SELECT {REDIRECT_COLUMNS} FROM redirects
+            WHERE id = (SELECT redirect_id FROM redirect_aliases WHERE alias = $1)
+            AND deleted_at IS NULL;"
+        ))
+        .bind(alias)
+        .fetch_one(&self.db)
+        .await
+    }
+
+    async fn read_secondary_aliases_by_redirect_ids(
+        &self,
+        redirect_ids: &[String],
+    ) -> Result<Vec<RedirectAlias>, sqlx::Error> {
+        if redirect_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut query = QueryBuilder::<Sqlite>::new(
+            "SELECT redirect_id, alias FROM redirect_aliases WHERE redirect_id IN (",
+        );
+        let mut ids = query.separated(", ");
+        for id in redirect_ids {
+            ids.push_bind(id.clone());
+        }
+        query.push(") ORDER BY alias;");
+        query
+            .build_query_as::<RedirectAlias>()
+            .fetch_all(&self.db)
+            .await
+    }
+
+    async fn create_secondary_alias(
+        &self,
+        redirect_id: &str,
+        alias: &str,
+        created_at: i64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO redirect_aliases (alias, alias_key, redirect_id, created_at)
+            VALUES ($1, lower($1), $2, $3);",
         )
         .bind(alias)
+        .bind(redirect_id)
+        .bind(created_at)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_secondary_alias(
+        &self,
+        redirect_id: &str,
+        alias: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let result =
+            sqlx::query("DELETE FROM redirect_aliases WHERE redirect_id = $1 AND alias = $2;")
+                .bind(redirect_id)
+                .bind(alias)
+                .execute(&self.db)
+                .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn read_redirect_history(
+        &self,
+        redirect_id: &str,
+    ) -> Result<Vec<RedirectHistoryEntryDTO>, sqlx::Error> {
+        sqlx::query_as::<_, RedirectHistoryEntryDTO>(
+            "SELECT id, old_url, new_url, changed_by, changed_at FROM redirect_history
+            WHERE redirect_id = $1 ORDER BY id DESC;",
+        )
+        .bind(redirect_id)
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn read_redirect_history_entry(
+        &self,
+        redirect_id: &str,
+        entry_id: i64,
+    ) -> Result<RedirectHistoryEntryDTO, sqlx::Error> {
+        sqlx::query_as::<_, RedirectHistoryEntryDTO>(
+            "SELECT id, old_url, new_url, changed_by, changed_at FROM redirect_history
+            WHERE redirect_id = $1 AND id = $2;",
+        )
+        .bind(redirect_id)
+        .bind(entry_id)
         .fetch_one(&self.db)
         .await
     }
 
+    async fn resolve_alias_collisions(&self) -> Result<Vec<AliasCollision>, sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+        // the keys themselves are handed over by triggers, resolved collisions are forgotten
+        sqlx::query(
+            "DELETE FROM alias_collisions
+            WHERE redirect_id NOT IN (SELECT id FROM redirects WHERE lower(alias) = alias_collisions.alias_key)
+            OR (SELECT COUNT(*) FROM redirects r WHERE lower(r.alias) = alias_collisions.alias_key) < 2;",
+        )
+        .execute(&mut *tx)
+        .await?;
+        let collisions = sqlx::query_as::<_, AliasCollision>(
+            "SELECT c.alias_key, r.alias FROM alias_collisions c
+            JOIN redirects r ON r.id = c.redirect_id ORDER BY c.alias_key, r.alias;",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(collisions)
+    }
+
     async fn create_redirect(&self, redirect: &Redirect) -> Result<(), sqlx::Error> {
-        sqlx::query("INSERT INTO redirects (id, alias, url, owner) VALUES ($1, $2, $3, $4);")
-            .bind(&redirect.id)
+        insert_redirect(&self.db, redirect, "").await?;
+        Ok(())
+    }
+
+    async fn create_redirects(
+        &self,
+        redirects: &[Redirect],
+        dry_run: bool,
+    ) -> Result<Vec<bool>, sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+        let mut created = Vec::with_capacity(redirects.len());
+        for redirect in redirects {
+            // taken aliases, including duplicates within the import, are skipped
+            let secondary_taken: bool = sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM redirect_aliases WHERE alias_key = lower($1));",
+            )
             .bind(&redirect.alias)
-            .bind(&redirect.url)
-            .bind(&redirect.owner)
-            .execute(&self.db)
+            .fetch_one(&mut *tx)
             .await?;
-        Ok(())
+            if secondary_taken {
+                created.push(false);
+                continue;
+            }
+            let inserted = insert_redirect(&mut *tx, redirect, "OR IGNORE").await?;
+            created.push(inserted > 0);
+        }
+        if dry_run {
+            tx.rollback().await?;
+        } else {
+            tx.commit().await?;
+        }
+        Ok(created)
     }
 
-    async fn read_all_redirects(&self) -> Result<Vec<Redirect>, sqlx::Error> {
-        let redirects =
-            sqlx::query_as::<_, Redirect>("SELECT id, alias, url, owner FROM redirects;")
-                .fetch_all(&self.db)
-                .await?;
-        Ok(redirects)
+    async fn read_all_redirects(
+        &self,
+        page: &RedirectPageQuery,
+    ) -> Result<RedirectPage, sqlx::Error> {
+        self.read_redirects_page(None, page).await
     }
 
     async fn read_all_redirects_by_user_id(
         &self,
         user_id: &str,
-    ) -> Result<Vec<Redirect>, sqlx::Error> {
-        let redirects = sqlx::query_as::<_, Redirect>(
-            "SELECT id, alias, url, owner FROM redirects WHERE owner = $1;",
-        )
-        .bind(user_id)
-        .fetch_all(&self.db)
-        .await?;
-        Ok(redirects)
+        page: &RedirectPageQuery,
+    ) -> Result<RedirectPage, sqlx::Error> {
+        self.read_redirects_page(Some(user_id), page).await
     }
 
     async fn delete_redirect_by_alias(&self, alias: &str) -> Result<u64, sqlx::Error> {
-        let result = sqlx::query("DELETE FROM redirects WHERE alias = $1;")
-            .bind(alias)
-            .execute(&self.db)
-            .await?;
+        let now = now_unix();
+        let result = sqlx::query(
+            "UPDATE redirects SET deleted_at = $2 WHERE alias = $1 AND deleted_at IS NULL;",
+        )
+        .bind(alias)
+        .bind(now)
+        .execute(&self.db)
+        .await?;
         Ok(result.rows_affected())
     }
 
     async fn delete_redirect_by_id(&self, id: &str) -> Result<u64, sqlx::Error> {
-        let result = sqlx::query("DELETE FROM redirects WHERE id = $1;")
-            .bind(id)
-            .execute(&self.db)
-            .await?;
+        let now = now_unix();
+        let result = sqlx::query(
+            "UPDATE redirects SET deleted_at = $2 WHERE id = $1 AND deleted_at IS NULL;",
+        )
+        .bind(id)
+        .bind(now)
+        .execute(&self.db)
+        .await?;
         Ok(result.rows_affected())
     }
 
@@ -80,38 +475,181 @@ impl RedirectRepo for RedirectRepoSqliteImpl {
         alias: &str,
         user_id: &str,
     ) -> Result<u64, sqlx::Error> {
-        let result = sqlx::query("DELETE FROM redirects WHERE alias = $1 AND owner = $2;")
-            .bind(alias)
-            .bind(user_id)
-            .execute(&self.db)
-            .await?;
+        let now = now_unix();
+        let result = sqlx::query(
+            "UPDATE redirects SET deleted_at = $3
+            WHERE alias = $1 AND owner = $2 AND deleted_at IS NULL;",
+        )
+        .bind(alias)
+        .bind(user_id)
+        .bind(now)
+        .execute(&self.db)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn restore_redirect_by_id(&self, id: &str) -> Result<u64, sqlx::Error> {
+        // redirects of a deleted user can only come back together with their owner
+        let result = sqlx::query(
+            "UPDATE redirects SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL
+            AND owner IN (SELECT id FROM users WHERE deleted_at IS NULL);",
+        )
+        .bind(id)
+        .execute(&self.db)
+        .await?;
         Ok(result.rows_affected())
     }
 
     async fn update_redirect_by_alias(
         &self,
         alias: &str,
-        redirect: &UpdateUrlDTO,
+        redirect: &UpdateRedirectDTO,
+        pwhash: Option<Option<&str>>,
         user_id: &str,
-    ) -> Result<u64, sqlx::Error> {
-        let result = sqlx::query("UPDATE redirects SET url = $1 WHERE alias = $2 AND owner = $3;")
-            .bind(&redirect.url)
-            .bind(alias)
-            .bind(user_id)
-            .execute(&self.db)
+        validate: &RedirectValidator,
+    ) -> Result<u64, RedirectUpdateError> {
+        let now = now_unix();
+        let mut tx = self.db.begin().await?;
+        let result = sqlx::query(
+            "UPDATE redirects SET alias = COALESCE($1, alias),
+            alias_key = CASE WHEN $1 IS NULL THEN alias_key ELSE lower($1) END, url = COALESCE($2, url),
+            owner = COALESCE($3, owner), redirect_type = COALESCE($4, redirect_type),
+            passthrough = COALESCE($8, passthrough), template = COALESCE($9, template),
+            query_params = COALESCE($10, query_params),
+            case_insensitive = CASE WHEN $18 THEN $11 ELSE case_insensitive END, preview = COALESCE($12, preview),
+            active_from = CASE WHEN $19 THEN $13 ELSE active_from END,
+            active_until = CASE WHEN $20 THEN $14 ELSE active_until END,
+            fallback_url = CASE WHEN $21 THEN $15 ELSE fallback_url END,
+            pwhash = CASE WHEN $16 THEN $17 ELSE pwhash END,
+            updated_at = $7, updated_by = $6
+            WHERE alias = $5 AND owner = $6 AND deleted_at IS NULL;",
+        )
+        .bind(&redirect.alias)
+        .bind(&redirect.url)
+        .bind(&redirect.owner)
+        .bind(redirect.redirect_type.map(u16::from))
+        .bind(alias)
+        .bind(user_id)
+        .bind(now)
+        .bind(redirect.passthrough)
+        .bind(redirect.template)
+        .bind(redirect.query_params.as_ref().map(Json))
+        .bind(redirect.case_insensitive.flatten())
+        .bind(redirect.preview)
+        .bind(redirect.active_from.flatten())
+        .bind(redirect.active_until.flatten())
+        .bind(redirect.fallback_url.as_ref().and_then(Option::as_deref))
+        .bind(pwhash.is_some())
+        .bind(pwhash.flatten())
+        .bind(redirect.case_insensitive.is_some())
+        .bind(redirect.active_from.is_some())
+        .bind(redirect.active_until.is_some())
+        .bind(redirect.fallback_url.is_some())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(e) if e.is_foreign_key_violation() => {
+                RedirectUpdateError::UnknownOwner
+            }
+            e => RedirectUpdateError::Db(e),
+        })?;
+        if result.rows_affected() == 0 {
+            return Ok(0);
+        }
+        // the foreign key can't tell a user in the trash apart from an active one
+        if let Some(owner) = &redirect.owner {
+            let owner_active: bool = sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM users WHERE id = $1 AND deleted_at IS NULL);",
+            )
+            .bind(owner)
+            .fetch_one(&mut *tx)
             .await?;
+            if !owner_active {
+                return Err(RedirectUpdateError::UnknownOwner);
+            }
+        }
+        // the merged row is validated before the commit, so no other write can slip in between
+        let updated = sqlx::query_as::<_, Redirect>(&format!(
+            "SELECT {REDIRECT_COLUMNS} FROM redirects WHERE alias = $1;"
+        ))
+        .bind(redirect.alias.as_deref().unwrap_or(alias))
+        .fetch_one(&mut *tx)
+        .await?;
+        validate(&updated).map_err(RedirectUpdateError::Invalid)?;
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn update_redirect_url_by_id(
+        &self,
+        id: &str,
+        url: &str,
+        user_id: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let now = now_unix();
+        let result = sqlx::query(
+            "UPDATE redirects SET url = $1, updated_at = $2, updated_by = $3 WHERE id = $4;",
+        )
+        .bind(url)
+        .bind(now)
+        .bind(user_id)
+        .bind(id)
+        .execute(&self.db)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn update_redirect_enabled_by_id(
+        &self,
+        id: &str,
+        enabled: bool,
+        locked: bool,
+        user_id: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let now = now_unix();
+        let result = sqlx::query(
+            "UPDATE redirects SET enabled = $1, locked = $2, updated_at = $3, updated_by = $4
+            WHERE id = $5 AND deleted_at IS NULL;",
+        )
+        .bind(enabled)
+        .bind(locked)
+        .bind(now)
+        .bind(user_id)
+        .bind(id)
+        .execute(&self.db)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn consume_redirect_click(&self, id: &str) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE redirects SET remaining_clicks = remaining_clicks - 1
+            WHERE id = $1 AND remaining_clicks > 0;",
+        )
+        .bind(id)
+        .execute(&self.db)
+        .await?;
         Ok(result.rows_affected())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
     use sqlx::SqlitePool;
+    use tokio::task::JoinSet;
     use uuid::Uuid;
 
     use crate::{
-        data::{RedirectRepo, RedirectRepoSqliteImpl},
-        model::{Redirect, RedirectDTO, UpdateUrlDTO, User},
+        data::{
+            RedirectRepo, RedirectRepoSqliteImpl, RedirectUpdateError, TagRepo, TagRepoSqliteImpl,
+        },
+        model::{
+            Redirect, RedirectDTO, RedirectPageQuery, RedirectSortKey, RedirectType, SortOrder,
+            UpdateRedirectDTO, User,
+        },
+        service::DbServiceError,
     };
 
     async fn setup_test_db() -> SqlitePool {
@@ -138,18 +676,21 @@ mod tests {
                 alias: "somealias".to_string(),
                 url: "https://someurl.com".to_string(),
                 owner: owner.id.clone(),
+                ..Default::default()
             },
             Redirect {
                 id: Uuid::new_v4().to_string(),
                 alias: "secondalias".to_string(),
                 url: "https://secondurl.com".to_string(),
                 owner: owner.id.clone(),
+                ..Default::default()
             },
             Redirect {
                 id: Uuid::new_v4().to_string(),
                 alias: "thirdalias".to_string(),
                 url: "https://thirdurl.com".to_string(),
                 owner: owner.id.clone(),
+                ..Default::default()
             },
         ];
 
@@ -160,14 +701,16 @@ mod tests {
     }
 
     async fn read_from_test_db(alias: &str, pool: &SqlitePool) -> Result<RedirectDTO, sqlx::Error> {
-        sqlx::query_as::<_, RedirectDTO>("SELECT alias, url FROM redirects WHERE alias = $1;")
-            .bind(alias)
-            .fetch_one(pool)
-            .await
+        sqlx::query_as::<_, RedirectDTO>(
+            "SELECT alias, url, enabled FROM redirects WHERE alias = $1;",
+        )
+        .bind(alias)
+        .fetch_one(pool)
+        .await
     }
 
     async fn insert_into_test_db(redirect: &Redirect, pool: &SqlitePool) {
-        sqlx::query("INSERT INTO redirects (id, alias, url, owner) VALUES ($1, $2, $3, $4);")
+        sqlx::query("INSERT INTO redirects (id, alias, alias_key, url, owner) VALUES ($1, $2, lower($2), $3, $4);")
             .bind(&redirect.id)
             .bind(&redirect.alias)
             .bind(&redirect.url)
@@ -188,10 +731,12 @@ mod tests {
     }
 
     async fn read_all_from_test_db(pool: &SqlitePool) -> Vec<RedirectDTO> {
-        sqlx::query_as::<_, RedirectDTO>("SELECT alias, url FROM redirects;")
-            .fetch_all(pool)
-            .await
-            .unwrap()
+        sqlx::query_as::<_, RedirectDTO>(
+            "SELECT alias, url, enabled FROM redirects WHERE deleted_at IS NULL;",
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap()
     }
 
     #[tokio::test]
@@ -204,6 +749,7 @@ mod tests {
             alias: "somenewalias".to_string(),
             url: "https://someurl.com".to_string(),
             owner: owner.id,
+            ..Default::default()
         };
 
         let result = repo.create_redirect(&redirect).await;
@@ -215,6 +761,27 @@ mod tests {
         assert_eq!(fetched.url, "https://someurl.com");
     }
 
+    #[tokio::test]
+    async fn test_create_redirect_with_expiry_success() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let (_, owner) = seed_test_db(&pool).await;
+        let redirect = Redirect {
+            id: Uuid::new_v4().to_string(),
+            alias: "somenewalias".to_string(),
+            url: "https://someurl.com".to_string(),
+            owner: owner.id,
+            expires_at: Some(1772897393),
+            ..Default::default()
+        };
+
+        let result = repo.create_redirect(&redirect).await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        let fetched = repo.read_redirect_by_alias(&redirect.alias).await.unwrap();
+        assert_eq!(fetched, redirect);
+    }
+
     #[tokio::test]
     async fn test_create_redirect_with_unknown_user_fails() {
         let pool = setup_test_db().await;
@@ -225,6 +792,7 @@ mod tests {
             alias: "somenewalias".to_string(),
             url: "https://someurl.com".to_string(),
             owner: "some_none_existant_user_id".to_owned(),
+            ..Default::default()
         };
 
         let result = repo.create_redirect(&dto).await;
@@ -243,6 +811,7 @@ mod tests {
             alias: dtos[0].alias.clone(),
             url: dtos[0].url.clone(),
             owner: dtos[0].owner.clone(),
+            ..Default::default()
         };
 
         let result = repo.create_redirect(&duplicate).await;
@@ -256,11 +825,18 @@ mod tests {
         let repo = RedirectRepoSqliteImpl::new(pool.clone());
 
         let (aliases, _) = seed_test_db(&pool).await;
-        let update_dto = UpdateUrlDTO {
-            url: "https://someotherurl.com".to_string(),
+        let update_dto = UpdateRedirectDTO {
+            url: Some("https://someotherurl.com".to_string()),
+            ..Default::default()
         };
         let result = repo
-            .update_redirect_by_alias(&aliases[0].alias, &update_dto, &aliases[0].owner)
+            .update_redirect_by_alias(
+                &aliases[0].alias,
+                &update_dto,
+                None,
+                &aliases[0].owner,
+                &|_| Ok(()),
+            )
             .await;
         dbg!(result.as_ref().err());
         assert!(result.is_ok());
@@ -268,7 +844,7 @@ mod tests {
         let updated = read_from_test_db(&aliases[0].alias, &pool).await.unwrap();
 
         assert_eq!(updated.alias, aliases[0].alias);
-        assert_eq!(updated.url, update_dto.url);
+        assert_eq!(Some(updated.url), update_dto.url);
     }
 
     #[tokio::test]
@@ -277,11 +853,18 @@ mod tests {
         let repo = RedirectRepoSqliteImpl::new(pool.clone());
 
         let (aliases, _) = seed_test_db(&pool).await;
-        let update_dto = UpdateUrlDTO {
-            url: "https://someotherurl.com".to_string(),
+        let update_dto = UpdateRedirectDTO {
+            url: Some("https://someotherurl.com".to_string()),
+            ..Default::default()
         };
         let result = repo
-            .update_redirect_by_alias(&aliases[0].alias, &update_dto, &Uuid::new_v4().to_string())
+            .update_redirect_by_alias(
+                &aliases[0].alias,
+                &update_dto,
+                None,
+                &Uuid::new_v4().to_string(),
+                &|_| Ok(()),
+            )
             .await;
         dbg!(result.as_ref().err());
         assert!(result.is_ok());
@@ -294,11 +877,18 @@ mod tests {
         let repo = RedirectRepoSqliteImpl::new(pool.clone());
 
         let (aliases, _) = seed_test_db(&pool).await;
-        let update_dto = UpdateUrlDTO {
-            url: "https://someotherurl.com".to_string(),
+        let update_dto = UpdateRedirectDTO {
+            url: Some("https://someotherurl.com".to_string()),
+            ..Default::default()
         };
         let result = repo
-            .update_redirect_by_alias("somewrongalias", &update_dto, &aliases[0].owner)
+            .update_redirect_by_alias(
+                "somewrongalias",
+                &update_dto,
+                None,
+                &aliases[0].owner,
+                &|_| Ok(()),
+            )
             .await;
         dbg!(result.as_ref().err());
         assert!(result.is_ok());
@@ -307,6 +897,106 @@ mod tests {
         assert!(notfound.is_err());
     }
 
+    #[tokio::test]
+    async fn test_update_redirect_passthrough_success() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+
+        let (aliases, _) = seed_test_db(&pool).await;
+        let update_dto = UpdateRedirectDTO {
+            passthrough: Some(true),
+            ..Default::default()
+        };
+        let result = repo
+            .update_redirect_by_alias(
+                &aliases[0].alias,
+                &update_dto,
+                None,
+                &aliases[0].owner,
+                &|_| Ok(()),
+            )
+            .await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+
+        let updated = repo
+            .read_redirect_by_alias(&aliases[0].alias)
+            .await
+            .unwrap();
+        assert!(updated.passthrough);
+        assert_eq!(updated.url, aliases[0].url);
+
+        let untouched = repo
+            .read_redirect_by_alias(&aliases[1].alias)
+            .await
+            .unwrap();
+        assert!(!untouched.passthrough);
+    }
+
+    #[tokio::test]
+    async fn test_query_params_roundtrip() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+
+        let (aliases, user) = seed_test_db(&pool).await;
+        let redirect = Redirect {
+            id: Uuid::new_v4().to_string(),
+            alias: "campaign".to_string(),
+            url: "https://someurl.com".to_string(),
+            owner: user.id.clone(),
+            query_params: [("utm_source".to_owned(), "news".to_owned())].into(),
+            ..Default::default()
+        };
+        repo.create_redirect(&redirect).await.unwrap();
+        let created = repo.read_redirect_by_alias("campaign").await.unwrap();
+        assert_eq!(created.query_params, redirect.query_params);
+        let seeded = repo
+            .read_redirect_by_alias(&aliases[0].alias)
+            .await
+            .unwrap();
+        assert!(seeded.query_params.is_empty());
+
+        let update_dto = UpdateRedirectDTO {
+            query_params: Some(BTreeMap::new()),
+            ..Default::default()
+        };
+        let result = repo
+            .update_redirect_by_alias("campaign", &update_dto, None, &user.id, &|_| Ok(()))
+            .await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        let updated = repo.read_redirect_by_alias("campaign").await.unwrap();
+        assert!(updated.query_params.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_update_redirect_url_by_id_success() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+
+        let (aliases, _) = seed_test_db(&pool).await;
+        let result = repo
+            .update_redirect_url_by_id(&aliases[0].id, "https://someotherurl.com", "admin_id")
+            .await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), 1);
+
+        let updated = repo
+            .read_redirect_by_alias(&aliases[0].alias)
+            .await
+            .unwrap();
+        assert_eq!(updated.url, "https://someotherurl.com");
+        assert_eq!(updated.owner, aliases[0].owner);
+        assert_eq!(updated.updated_by.as_deref(), Some("admin_id"));
+
+        let unknown = repo
+            .update_redirect_url_by_id("unknown_id", "https://someotherurl.com", "admin_id")
+            .await
+            .unwrap();
+        assert_eq!(unknown, 0);
+    }
+
     #[tokio::test]
     async fn test_read_redirect_by_alias_success() {
         let pool = setup_test_db().await;
@@ -338,7 +1028,10 @@ mod tests {
         let pool = setup_test_db().await;
         let repo = RedirectRepoSqliteImpl::new(pool.clone());
 
-        let result = repo.read_all_redirects().await;
+        let result = repo
+            .read_all_redirects(&RedirectPageQuery::default())
+            .await
+            .map(|p| p.redirects);
 
         dbg!(result.as_ref().err());
         assert!(result.is_ok());
@@ -353,7 +1046,10 @@ mod tests {
 
         let (redirects, _) = seed_test_db(&pool).await;
 
-        let result = repo.read_all_redirects().await;
+        let result = repo
+            .read_all_redirects(&RedirectPageQuery::default())
+            .await
+            .map(|p| p.redirects);
 
         dbg!(result.as_ref().err());
         assert!(result.is_ok());
@@ -388,17 +1084,25 @@ mod tests {
             alias: "the_newest_alias".to_owned(),
             url: "http://url.de".to_owned(),
             owner: new_user.id.clone(),
+            ..Default::default()
         };
         insert_into_test_db(&new_redirect, &pool).await;
 
-        let redirects_result = repo.read_all_redirects_by_user_id(&new_user.id).await;
+        let redirects_result = repo
+            .read_all_redirects_by_user_id(&new_user.id, &RedirectPageQuery::default())
+            .await
+            .map(|p| p.redirects);
         dbg!(redirects_result.as_ref().err());
         assert!(redirects_result.is_ok());
         let limited_redirect_list = redirects_result.unwrap();
         assert!(!limited_redirect_list.is_empty());
         assert_eq!(limited_redirect_list.len(), 1);
         assert_eq!(limited_redirect_list[0], new_redirect);
-        let full_list = repo.read_all_redirects().await.unwrap();
+        let full_list = repo
+            .read_all_redirects(&RedirectPageQuery::default())
+            .await
+            .unwrap()
+            .redirects;
         assert_eq!(full_list.len(), seeded_list.len() + 1);
     }
 
@@ -512,4 +1216,867 @@ mod tests {
         let db_list = read_all_from_test_db(&pool).await;
         assert_eq!(db_list.len(), dtos.len())
     }
+
+    #[tokio::test]
+    async fn test_trash_expired_redirects_only_trashes_expired() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+
+        let (dtos, owner) = seed_test_db(&pool).await;
+        let expired = Redirect {
+            id: Uuid::new_v4().to_string(),
+            alias: "expiredalias".to_owned(),
+            url: "https://expiredurl.com".to_owned(),
+            owner: owner.id.clone(),
+            expires_at: Some(100),
+            ..Default::default()
+        };
+        let not_expired = Redirect {
+            id: Uuid::new_v4().to_string(),
+            alias: "notexpiredalias".to_owned(),
+            url: "https://notexpiredurl.com".to_owned(),
+            owner: owner.id.clone(),
+            expires_at: Some(300),
+            ..Default::default()
+        };
+        repo.create_redirect(&expired).await.unwrap();
+        repo.create_redirect(&not_expired).await.unwrap();
+
+        let result = repo.trash_expired_redirects(200, 400).await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), 1);
+        let remaining = repo
+            .read_all_redirects(&RedirectPageQuery::default())
+            .await
+            .unwrap()
+            .redirects;
+        assert_eq!(remaining.len(), dtos.len() + 1);
+        assert!(remaining.contains(&not_expired));
+        assert!(!remaining.contains(&expired));
+
+        // the expired redirect waits in the trash like a deleted one
+        let trashed = repo
+            .read_deleted_redirect_by_alias("expiredalias")
+            .await
+            .unwrap();
+        assert_eq!(trashed.deleted_at, Some(400));
+        assert_eq!(repo.trash_expired_redirects(200, 500).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_create_redirect_with_max_clicks_sets_remaining_clicks() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let (_, owner) = seed_test_db(&pool).await;
+        let redirect = Redirect {
+            id: Uuid::new_v4().to_string(),
+            alias: "limitedalias".to_owned(),
+            url: "https://limitedurl.com".to_owned(),
+            owner: owner.id,
+            max_clicks: Some(3),
+            ..Default::default()
+        };
+        repo.create_redirect(&redirect).await.unwrap();
+
+        let fetched = repo.read_redirect_by_alias(&redirect.alias).await.unwrap();
+        assert_eq!(fetched.max_clicks, Some(3));
+        assert_eq!(fetched.remaining_clicks, Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_consume_redirect_click_stops_at_zero() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let (_, owner) = seed_test_db(&pool).await;
+        let redirect = Redirect {
+            id: Uuid::new_v4().to_string(),
+            alias: "limitedalias".to_owned(),
+            url: "https://limitedurl.com".to_owned(),
+            owner: owner.id,
+            max_clicks: Some(1),
+            ..Default::default()
+        };
+        repo.create_redirect(&redirect).await.unwrap();
+
+        assert_eq!(repo.consume_redirect_click(&redirect.id).await.unwrap(), 1);
+        assert_eq!(repo.consume_redirect_click(&redirect.id).await.unwrap(), 0);
+        let fetched = repo.read_redirect_by_alias(&redirect.alias).await.unwrap();
+        assert_eq!(fetched.remaining_clicks, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_consume_redirect_click_without_limit_leads_to_no_updates() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let (dtos, _) = seed_test_db(&pool).await;
+
+        let result = repo.consume_redirect_click(&dtos[0].id).await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_consume_redirect_click_never_exceeds_limit() {
+        let pool = setup_test_db().await;
+        let repo = Arc::new(RedirectRepoSqliteImpl::new(pool.clone()));
+        let (_, owner) = seed_test_db(&pool).await;
+        let redirect = Redirect {
+            id: Uuid::new_v4().to_string(),
+            alias: "limitedalias".to_owned(),
+            url: "https://limitedurl.com".to_owned(),
+            owner: owner.id,
+            max_clicks: Some(5),
+            ..Default::default()
+        };
+        repo.create_redirect(&redirect).await.unwrap();
+
+        let mut join_set = JoinSet::new();
+        for _ in 0..20 {
+            let r = Arc::clone(&repo);
+            let id = redirect.id.clone();
+            join_set.spawn(async move { r.consume_redirect_click(&id).await.unwrap() });
+        }
+        let mut consumed = 0;
+        while let Some(result) = join_set.join_next().await {
+            consumed += result.unwrap();
+        }
+
+        assert_eq!(consumed, 5);
+        let fetched = repo.read_redirect_by_alias(&redirect.alias).await.unwrap();
+        assert_eq!(fetched.remaining_clicks, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_create_redirect_with_redirect_type_success() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let (_, owner) = seed_test_db(&pool).await;
+        let redirect = Redirect {
+            id: Uuid::new_v4().to_string(),
+            alias: "permanentalias".to_owned(),
+            url: "https://permanenturl.com".to_owned(),
+            owner: owner.id,
+            redirect_type: RedirectType::MovedPermanently,
+            ..Default::default()
+        };
+        repo.create_redirect(&redirect).await.unwrap();
+
+        let fetched = repo.read_redirect_by_alias(&redirect.alias).await.unwrap();
+        assert_eq!(fetched.redirect_type, RedirectType::MovedPermanently);
+    }
+
+    #[tokio::test]
+    async fn test_update_redirect_without_redirect_type_keeps_redirect_type() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let (_, owner) = seed_test_db(&pool).await;
+        let redirect = Redirect {
+            id: Uuid::new_v4().to_string(),
+            alias: "permanentalias".to_owned(),
+            url: "https://permanenturl.com".to_owned(),
+            owner: owner.id.clone(),
+            redirect_type: RedirectType::PermanentRedirect,
+            ..Default::default()
+        };
+        repo.create_redirect(&redirect).await.unwrap();
+
+        let update_dto = UpdateRedirectDTO {
+            url: Some("https://someotherurl.com".to_string()),
+            ..Default::default()
+        };
+        repo.update_redirect_by_alias(&redirect.alias, &update_dto, None, &owner.id, &|_| Ok(()))
+            .await
+            .unwrap();
+        let fetched = repo.read_redirect_by_alias(&redirect.alias).await.unwrap();
+        assert_eq!(Some(fetched.url), update_dto.url);
+        assert_eq!(fetched.redirect_type, RedirectType::PermanentRedirect);
+
+        let update_dto = UpdateRedirectDTO {
+            url: Some("https://someotherurl.com".to_string()),
+            redirect_type: Some(RedirectType::Found),
+            ..Default::default()
+        };
+        repo.update_redirect_by_alias(&redirect.alias, &update_dto, None, &owner.id, &|_| Ok(()))
+            .await
+            .unwrap();
+        let fetched = repo.read_redirect_by_alias(&redirect.alias).await.unwrap();
+        assert_eq!(fetched.redirect_type, RedirectType::Found);
+    }
+
+    #[tokio::test]
+    async fn test_update_redirect_renames_alias_and_transfers_owner() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+
+        let (aliases, owner) = seed_test_db(&pool).await;
+        let new_owner = User {
+            id: Uuid::new_v4().to_string(),
+            name: "otheruser".to_owned(),
+            ..get_test_user_data()
+        };
+        insert_user_into_test_db(&new_owner, &pool).await;
+        let update_dto = UpdateRedirectDTO {
+            alias: Some("renamedalias".to_owned()),
+            owner: Some(new_owner.id.clone()),
+            ..Default::default()
+        };
+        let result = repo
+            .update_redirect_by_alias(&aliases[0].alias, &update_dto, None, &owner.id, &|_| Ok(()))
+            .await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), 1);
+
+        assert!(read_from_test_db(&aliases[0].alias, &pool).await.is_err());
+        let updated = repo.read_redirect_by_alias("renamedalias").await.unwrap();
+        assert_eq!(updated.id, aliases[0].id);
+        assert_eq!(updated.url, aliases[0].url);
+        assert_eq!(updated.owner, new_owner.id);
+    }
+
+    #[tokio::test]
+    async fn test_update_redirect_to_existing_alias_fails() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+
+        let (aliases, owner) = seed_test_db(&pool).await;
+        let update_dto = UpdateRedirectDTO {
+            alias: Some(aliases[1].alias.clone()),
+            url: Some("https://someotherurl.com".to_owned()),
+            ..Default::default()
+        };
+        let result = repo
+            .update_redirect_by_alias(&aliases[0].alias, &update_dto, None, &owner.id, &|_| Ok(()))
+            .await;
+        assert!(
+            matches!(result, Err(RedirectUpdateError::Db(sqlx::Error::Database(e))) if e.is_unique_violation())
+        );
+
+        let unchanged = read_from_test_db(&aliases[0].alias, &pool).await.unwrap();
+        assert_eq!(unchanged.url, aliases[0].url);
+    }
+
+    #[tokio::test]
+    async fn test_update_redirect_to_unknown_owner_fails() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+
+        let (aliases, owner) = seed_test_db(&pool).await;
+        let update_dto = UpdateRedirectDTO {
+            owner: Some(Uuid::new_v4().to_string()),
+            ..Default::default()
+        };
+        let result = repo
+            .update_redirect_by_alias(&aliases[0].alias, &update_dto, None, &owner.id, &|_| Ok(()))
+            .await;
+        assert!(matches!(result, Err(RedirectUpdateError::UnknownOwner)));
+
+        let unchanged = repo
+            .read_redirect_by_alias(&aliases[0].alias)
+            .await
+            .unwrap();
+        assert_eq!(unchanged.owner, owner.id);
+    }
+
+    #[tokio::test]
+    async fn test_read_all_redirects_paginates_with_cursor() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let (seeded, _) = seed_test_db(&pool).await;
+
+        let mut page_query = RedirectPageQuery {
+            limit: 2,
+            order: SortOrder::Desc,
+            ..Default::default()
+        };
+        let first = repo.read_all_redirects(&page_query).await;
+        dbg!(first.as_ref().err());
+        assert!(first.is_ok());
+        let first = first.unwrap();
+        assert_eq!(first.redirects.len(), 2);
+        assert!(first.next_cursor.is_some());
+
+        page_query.after = first.next_cursor;
+        let second = repo.read_all_redirects(&page_query).await.unwrap();
+        assert_eq!(second.redirects.len(), 1);
+        assert!(second.next_cursor.is_none());
+
+        let aliases: Vec<String> = first
+            .redirects
+            .iter()
+            .chain(second.redirects.iter())
+            .map(|r| r.alias.clone())
+            .collect();
+        let mut expected: Vec<String> = seeded.iter().map(|r| r.alias.clone()).collect();
+        expected.sort();
+        expected.reverse();
+        assert_eq!(aliases, expected);
+    }
+
+    #[tokio::test]
+    async fn test_read_all_redirects_sorted_by_clicks() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let (seeded, _) = seed_test_db(&pool).await;
+        for (clicks, redirect) in [(5, &seeded[0]), (1, &seeded[1]), (3, &seeded[2])] {
+            sqlx::query("UPDATE redirects SET clicks = $1 WHERE id = $2;")
+                .bind(clicks)
+                .bind(&redirect.id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let mut page_query = RedirectPageQuery {
+            limit: 1,
+            sort: RedirectSortKey::Clicks,
+            ..Default::default()
+        };
+        let mut clicks = Vec::new();
+        loop {
+            let page = repo.read_all_redirects(&page_query).await.unwrap();
+            clicks.extend(page.redirects.iter().map(|r| r.clicks));
+            match page.next_cursor {
+                Some(cursor) => page_query.after = Some(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(clicks, vec![1, 3, 5]);
+    }
+
+    #[tokio::test]
+    async fn test_read_all_redirects_by_user_id_filters_by_search() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let (seeded, owner) = seed_test_db(&pool).await;
+
+        let page_query = RedirectPageQuery {
+            search: Some("SECONDURL".to_owned()),
+            ..Default::default()
+        };
+        let result = repo
+            .read_all_redirects_by_user_id(&owner.id, &page_query)
+            .await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().redirects, vec![seeded[1].clone()]);
+
+        let page_query = RedirectPageQuery {
+            search: Some("%".to_owned()),
+            ..Default::default()
+        };
+        let result = repo
+            .read_all_redirects_by_user_id(&owner.id, &page_query)
+            .await
+            .unwrap();
+        assert!(result.redirects.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_update_redirect_sets_updated_at_and_updated_by() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let (_, owner) = seed_test_db(&pool).await;
+        let redirect = Redirect {
+            id: Uuid::new_v4().to_string(),
+            alias: "timestampalias".to_owned(),
+            url: "https://timestampurl.com".to_owned(),
+            owner: owner.id.clone(),
+            created_at: 100,
+            updated_at: 100,
+            ..Default::default()
+        };
+        repo.create_redirect(&redirect).await.unwrap();
+        let created = repo.read_redirect_by_alias(&redirect.alias).await.unwrap();
+        assert_eq!(created, redirect);
+
+        let update_dto = UpdateRedirectDTO {
+            url: Some("https://someotherurl.com".to_string()),
+            ..Default::default()
+        };
+        let result = repo
+            .update_redirect_by_alias(&redirect.alias, &update_dto, None, &owner.id, &|_| Ok(()))
+            .await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+
+        let updated = repo.read_redirect_by_alias(&redirect.alias).await.unwrap();
+        assert_eq!(updated.created_at, 100);
+        assert!(updated.updated_at > 100);
+        assert_eq!(updated.updated_by, Some(owner.id));
+    }
+
+    #[tokio::test]
+    async fn test_url_changes_are_recorded_in_history() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let (redirects, owner) = seed_test_db(&pool).await;
+        let redirect = &redirects[0];
+
+        let update_dto = UpdateRedirectDTO {
+            url: Some("https://first.example.com".to_string()),
+            ..Default::default()
+        };
+        repo.update_redirect_by_alias(&redirect.alias, &update_dto, None, &owner.id, &|_| Ok(()))
+            .await
+            .unwrap();
+        // unchanged urls and other fields don't create entries
+        repo.update_redirect_by_alias(&redirect.alias, &update_dto, None, &owner.id, &|_| Ok(()))
+            .await
+            .unwrap();
+        repo.update_redirect_url_by_id(&redirect.id, "https://second.example.com", "admin")
+            .await
+            .unwrap();
+
+        let history = repo.read_redirect_history(&redirect.id).await.unwrap();
+        dbg!(&history);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].old_url, "https://first.example.com");
+        assert_eq!(history[0].new_url, "https://second.example.com");
+        assert_eq!(history[0].changed_by.as_deref(), Some("admin"));
+        assert_eq!(history[1].old_url, redirect.url);
+        assert_eq!(history[1].changed_by, Some(owner.id));
+
+        let entry = repo
+            .read_redirect_history_entry(&redirect.id, history[1].id)
+            .await
+            .unwrap();
+        assert_eq!(entry, history[1]);
+        assert!(matches!(
+            repo.read_redirect_history_entry(&redirects[1].id, history[1].id)
+                .await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_all_redirects_sorted_by_created_at() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let (_, owner) = seed_test_db(&pool).await;
+        for (alias, created_at) in [("bbb", 300), ("aaa", 200), ("ccc", 100)] {
+            let redirect = Redirect {
+                id: Uuid::new_v4().to_string(),
+                alias: alias.to_owned(),
+                url: "https://someurl.com".to_owned(),
+                owner: owner.id.clone(),
+                created_at,
+                ..Default::default()
+            };
+            repo.create_redirect(&redirect).await.unwrap();
+        }
+
+        let page_query = RedirectPageQuery {
+            limit: 3,
+            sort: RedirectSortKey::Created,
+            order: SortOrder::Desc,
+            ..Default::default()
+        };
+        let page = repo.read_all_redirects(&page_query).await.unwrap();
+        let aliases: Vec<&str> = page.redirects.iter().map(|r| r.alias.as_str()).collect();
+        assert_eq!(aliases, vec!["bbb", "aaa", "ccc"]);
+        assert!(page.next_cursor.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_read_all_redirects_by_user_id_filters_by_tag() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let tag_repo = TagRepoSqliteImpl::new(pool.clone());
+        let (seeded, owner) = seed_test_db(&pool).await;
+        tag_repo
+            .add_tag_to_redirect(&seeded[1].id, "project-x")
+            .await
+            .unwrap();
+        tag_repo
+            .add_tag_to_redirect(&seeded[2].id, "project-y")
+            .await
+            .unwrap();
+
+        let page_query = RedirectPageQuery {
+            tag: Some("project-x".to_owned()),
+            ..Default::default()
+        };
+        let result = repo
+            .read_all_redirects_by_user_id(&owner.id, &page_query)
+            .await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().redirects, vec![seeded[1].clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_create_redirects_skips_taken_aliases() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let (seeded, owner) = seed_test_db(&pool).await;
+        let new_redirect = |alias: &str| Redirect {
+            id: Uuid::new_v4().to_string(),
+            alias: alias.to_owned(),
+            url: "https://importedurl.com".to_owned(),
+            owner: owner.id.clone(),
+            ..Default::default()
+        };
+        let redirects = vec![
+            new_redirect("importedalias"),
+            new_redirect(&seeded[0].alias),
+            new_redirect("importedalias"),
+        ];
+
+        let result = repo.create_redirects(&redirects, false).await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), vec![true, false, false]);
+
+        let imported = repo.read_redirect_by_alias("importedalias").await.unwrap();
+        assert_eq!(imported, redirects[0]);
+        let unchanged = repo.read_redirect_by_alias(&seeded[0].alias).await.unwrap();
+        assert_eq!(unchanged, seeded[0]);
+    }
+
+    #[tokio::test]
+    async fn test_create_redirects_dry_run_creates_nothing() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let (seeded, owner) = seed_test_db(&pool).await;
+        let redirects = vec![Redirect {
+            id: Uuid::new_v4().to_string(),
+            alias: "importedalias".to_owned(),
+            url: "https://importedurl.com".to_owned(),
+            owner: owner.id.clone(),
+            ..Default::default()
+        }];
+
+        let result = repo.create_redirects(&redirects, true).await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), vec![true]);
+
+        assert!(repo.read_redirect_by_alias("importedalias").await.is_err());
+        let all = repo
+            .read_all_redirects(&RedirectPageQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(all.redirects.len(), seeded.len());
+    }
+
+    #[tokio::test]
+    async fn test_read_redirect_by_alias_key_ignores_case() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let (seeded, owner) = seed_test_db(&pool).await;
+
+        let result = repo
+            .read_redirect_by_alias_key(&seeded[0].alias.to_uppercase())
+            .await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), seeded[0]);
+
+        let conflicting = Redirect {
+            id: Uuid::new_v4().to_string(),
+            alias: seeded[0].alias.to_uppercase(),
+            url: "https://someotherurl.com".to_owned(),
+            owner: owner.id.clone(),
+            ..Default::default()
+        };
+        let result = repo.create_redirect(&conflicting).await;
+        assert!(matches!(result, Err(sqlx::Error::Database(e)) if e.is_unique_violation()));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_alias_collisions_assigns_keys_once_unique() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let owner = get_test_user_data();
+        insert_user_into_test_db(&owner, &pool).await;
+        // rows as left behind by the migration for aliases that differ only in case
+        sqlx::query(
+            "INSERT INTO redirects (id, alias, alias_key, url, owner) VALUES
+            ('1', 'Docs', 'docs', 'https://docs.rs', $1), ('2', 'docs', 'docs:2', 'https://docs.rs', $1),
+            ('3', 'DOCS', 'docs:3', 'https://docs.rs', $1);
+            INSERT INTO alias_collisions (redirect_id, alias_key) VALUES ('1', 'docs'), ('2', 'docs'), ('3', 'docs');",
+        )
+        .bind(&owner.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let collisions = repo.resolve_alias_collisions().await.unwrap();
+        let aliases: Vec<_> = collisions.iter().map(|c| c.alias.as_str()).collect();
+        assert_eq!(aliases, vec!["DOCS", "Docs", "docs"]);
+        // the exact alias wins, otherwise the oldest redirect holds the key
+        assert_eq!(
+            repo.read_redirect_by_alias_key("docs").await.unwrap().id,
+            "2"
+        );
+        assert_eq!(
+            repo.read_redirect_by_alias_key("dOcS").await.unwrap().id,
+            "1"
+        );
+        let taken = sqlx::query("INSERT INTO redirects (id, alias, alias_key, url, owner) VALUES ('4', 'dOcS', 'docs', 'https://docs.rs', $1);")
+            .bind(&owner.id)
+            .execute(&pool)
+            .await;
+        assert!(taken.is_err());
+
+        let update_dto = UpdateRedirectDTO {
+            alias: Some("documentation".to_owned()),
+            ..Default::default()
+        };
+        repo.update_redirect_by_alias("Docs", &update_dto, None, &owner.id, &|_| Ok(()))
+            .await
+            .unwrap();
+        let collisions = repo.resolve_alias_collisions().await.unwrap();
+        let aliases: Vec<_> = collisions.iter().map(|c| c.alias.as_str()).collect();
+        assert_eq!(aliases, vec!["DOCS", "docs"]);
+        assert_eq!(
+            repo.read_redirect_by_alias_key("dOcS").await.unwrap().id,
+            "2"
+        );
+
+        sqlx::query("DELETE FROM redirects WHERE id = '2';")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let collisions = repo.resolve_alias_collisions().await.unwrap();
+        assert!(collisions.is_empty());
+        let resolved = repo.read_redirect_by_alias_key("dOcS").await.unwrap();
+        assert_eq!(resolved.id, "3");
+        assert_eq!(resolved.alias, "DOCS");
+    }
+
+    #[tokio::test]
+    async fn test_secondary_aliases_share_namespace_with_redirects() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let (seeded, owner) = seed_test_db(&pool).await;
+
+        let result = repo
+            .create_secondary_alias(&seeded[0].id, "otheralias", 100)
+            .await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        let resolved = repo
+            .read_redirect_by_secondary_alias("otheralias")
+            .await
+            .unwrap();
+        assert_eq!(resolved, seeded[0]);
+        let resolved = repo.read_redirect_by_alias_key("OtherAlias").await.unwrap();
+        assert_eq!(resolved, seeded[0]);
+
+        let taken_by_redirect = repo
+            .create_secondary_alias(&seeded[0].id, &seeded[1].alias.to_uppercase(), 100)
+            .await;
+        assert!(
+            matches!(taken_by_redirect, Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("1811"))
+        );
+        let taken_by_alias = repo
+            .create_secondary_alias(&seeded[1].id, "OTHERALIAS", 100)
+            .await;
+        assert!(matches!(taken_by_alias, Err(sqlx::Error::Database(e)) if e.is_unique_violation()));
+        let conflicting = Redirect {
+            id: Uuid::new_v4().to_string(),
+            alias: "otheralias".to_owned(),
+            url: "https://someotherurl.com".to_owned(),
+            owner: owner.id.clone(),
+            ..Default::default()
+        };
+        let result = repo.create_redirect(&conflicting).await;
+        assert!(
+            matches!(result, Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("1811"))
+        );
+        let update_dto = UpdateRedirectDTO {
+            alias: Some("otheralias".to_owned()),
+            ..Default::default()
+        };
+        let result = repo
+            .update_redirect_by_alias(&seeded[1].alias, &update_dto, None, &owner.id, &|_| Ok(()))
+            .await;
+        assert!(
+            matches!(result, Err(RedirectUpdateError::Db(sqlx::Error::Database(e))) if e.code().as_deref() == Some("1811"))
+        );
+        let imported = repo.create_redirects(&[conflicting], false).await.unwrap();
+        assert_eq!(imported, vec![false]);
+    }
+
+    #[tokio::test]
+    async fn test_secondary_aliases_are_removed_with_redirect() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let (seeded, _) = seed_test_db(&pool).await;
+        repo.create_secondary_alias(&seeded[0].id, "first", 100)
+            .await
+            .unwrap();
+        repo.create_secondary_alias(&seeded[0].id, "second", 100)
+            .await
+            .unwrap();
+        repo.create_secondary_alias(&seeded[1].id, "third", 100)
+            .await
+            .unwrap();
+
+        let ids = vec![seeded[0].id.clone(), seeded[1].id.clone()];
+        let aliases = repo
+            .read_secondary_aliases_by_redirect_ids(&ids)
+            .await
+            .unwrap();
+        let names: Vec<_> = aliases.iter().map(|a| a.alias.as_str()).collect();
+        assert_eq!(names, vec!["first", "second", "third"]);
+
+        assert_eq!(
+            repo.delete_secondary_alias(&seeded[1].id, "first")
+                .await
+                .unwrap(),
+            0
+        );
+        repo.delete_redirect_by_id(&seeded[0].id).await.unwrap();
+        repo.purge_deleted_redirects(i64::MAX).await.unwrap();
+        let aliases = repo
+            .read_secondary_aliases_by_redirect_ids(&ids)
+            .await
+            .unwrap();
+        assert_eq!(aliases.len(), 1);
+        assert!(
+            repo.read_redirect_by_secondary_alias("first")
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deleted_redirect_keeps_alias_until_purged() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+        let (seeded, owner) = seed_test_db(&pool).await;
+
+        assert_eq!(repo.delete_redirect_by_id(&seeded[0].id).await.unwrap(), 1);
+        assert_eq!(repo.delete_redirect_by_id(&seeded[0].id).await.unwrap(), 0);
+        assert!(matches!(
+            repo.read_redirect_by_alias(&seeded[0].alias).await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+        let trash = repo
+            .read_all_redirects(&RedirectPageQuery {
+                deleted: true,
+                ..Default::default()
+            })
+            .await
+            .unwrap()
+            .redirects;
+        assert_eq!(trash.len(), 1);
+        assert!(trash[0].deleted_at.is_some());
+
+        let reused = Redirect {
+            id: Uuid::new_v4().to_string(),
+            alias: seeded[0].alias.clone(),
+            url: "https://reused.com".to_owned(),
+            owner: owner.id,
+            ..Default::default()
+        };
+        assert!(repo.create_redirect(&reused).await.is_err());
+
+        assert_eq!(repo.restore_redirect_by_id(&seeded[0].id).await.unwrap(), 1);
+        assert!(repo.read_redirect_by_alias(&seeded[0].alias).await.is_ok());
+
+        repo.delete_redirect_by_id(&seeded[0].id).await.unwrap();
+        assert_eq!(repo.purge_deleted_redirects(0).await.unwrap(), 0);
+        assert_eq!(repo.purge_deleted_redirects(i64::MAX).await.unwrap(), 1);
+        let result = repo.create_redirect(&reused).await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_update_redirect_rolls_back_when_validation_fails() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+
+        let (aliases, owner) = seed_test_db(&pool).await;
+        let update_dto = UpdateRedirectDTO {
+            url: Some("https://someotherurl.com".to_owned()),
+            ..Default::default()
+        };
+        let result = repo
+            .update_redirect_by_alias(
+                &aliases[0].alias,
+                &update_dto,
+                Some(Some("somehash")),
+                &owner.id,
+                &|updated| {
+                    assert_eq!(updated.url, "https://someotherurl.com");
+                    assert_eq!(updated.pwhash.as_deref(), Some("somehash"));
+                    Err(DbServiceError::NotFoundError)
+                },
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(RedirectUpdateError::Invalid(DbServiceError::NotFoundError))
+        ));
+
+        let unchanged = repo
+            .read_redirect_by_alias(&aliases[0].alias)
+            .await
+            .unwrap();
+        assert_eq!(unchanged.url, aliases[0].url);
+        assert_eq!(unchanged.pwhash, None);
+        assert!(
+            repo.read_redirect_history(&aliases[0].id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let result = repo
+            .update_redirect_by_alias(
+                &aliases[0].alias,
+                &update_dto,
+                Some(Some("somehash")),
+                &owner.id,
+                &|_| Ok(()),
+            )
+            .await;
+        dbg!(result.as_ref().err());
+        assert_eq!(result.unwrap(), 1);
+        let updated = repo
+            .read_redirect_by_alias(&aliases[0].alias)
+            .await
+            .unwrap();
+        assert_eq!(updated.pwhash.as_deref(), Some("somehash"));
+    }
+
+    #[tokio::test]
+    async fn test_update_redirect_to_deleted_owner_fails() {
+        let pool = setup_test_db().await;
+        let repo = RedirectRepoSqliteImpl::new(pool.clone());
+
+        let (aliases, owner) = seed_test_db(&pool).await;
+        let new_owner = User {
+            id: Uuid::new_v4().to_string(),
+            name: "deleteduser".to_owned(),
+            ..get_test_user_data()
+        };
+        insert_user_into_test_db(&new_owner, &pool).await;
+        sqlx::query("UPDATE users SET deleted_at = 1 WHERE id = $1;")
+            .bind(&new_owner.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let update_dto = UpdateRedirectDTO {
+            owner: Some(new_owner.id.clone()),
+            ..Default::default()
+        };
+        let result = repo
+            .update_redirect_by_alias(&aliases[0].alias, &update_dto, None, &owner.id, &|_| Ok(()))
+            .await;
+        assert!(matches!(result, Err(RedirectUpdateError::UnknownOwner)));
+
+        let unchanged = repo
+            .read_redirect_by_alias(&aliases[0].alias)
+            .await
+            .unwrap();
+        assert_eq!(unchanged.owner, owner.id);
+    }
 }
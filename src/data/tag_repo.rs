@@ -0,0 +1,295 @@
+use async_trait::async_trait;
+use sqlx::{Pool, QueryBuilder, Sqlite};
+
+use crate::{data::TagRepo, model::RedirectTag};
+
+pub struct TagRepoSqliteImpl {
+    db: Pool<Sqlite>,
+}
+
+impl TagRepoSqliteImpl {
+    pub fn new(db: Pool<Sqlite>) -> Self {
+        TagRepoSqliteImpl { db }
+    }
+}
+
+#[async_trait]
+impl TagRepo for TagRepoSqliteImpl {
+    async fn add_tag_to_redirect(&self, redirect_id: &str, tag: &str) -> Result<u64, sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+        sqlx::query("INSERT OR IGNORE INTO tags (name) VALUES ($1);")
+            .bind(tag)
+            .execute(&mut *tx)
+            .await?;
+        let result = sqlx::query(
+            "INSERT OR IGNORE INTO redirect_tags (redirect_id, tag_id)
+            SELECT $1, id FROM tags WHERE name = $2;",
+        )
+        .bind(redirect_id)
+        .bind(tag)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn remove_tag_from_redirect(
+        &self,
+        redirect_id: &str,
+        tag: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+        let result = sqlx::query(
+            "DELETE FROM redirect_tags
+            WHERE redirect_id = $1 AND tag_id = (SELECT id FROM tags WHERE name = $2);",
+        )
+        .bind(redirect_id)
+        .bind(tag)
+        .execute(&mut *tx)
+        .await?;
+        // tags only exist as long as they are attached to at least one redirect
+        sqlx::query(
+            "DELETE FROM tags WHERE name = $1
+            AND NOT EXISTS (SELECT 1 FROM redirect_tags WHERE tag_id = tags.id);",
+        )
+        .bind(tag)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn read_tags_by_redirect_ids(
+        &self,
+        redirect_ids: &[String],
+    ) -> Result<Vec<RedirectTag>, sqlx::Error> {
+        if redirect_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut query = QueryBuilder::<Sqlite>::new(
+            "SELECT rt.redirect_id, t.name AS tag FROM redirect_tags rt
+            JOIN tags t ON t.id = rt.tag_id WHERE rt.redirect_id IN (",
+        );
+        let mut ids = query.separated(", ");
+        for id in redirect_ids {
+            ids.push_bind(id.clone());
+        }
+        query.push(") ORDER BY t.name;");
+        query
+            .build_query_as::<RedirectTag>()
+            .fetch_all(&self.db)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::SqlitePool;
+    use uuid::Uuid;
+
+    use crate::{
+        data::{TagRepo, TagRepoSqliteImpl},
+        model::{Redirect, RedirectTag, User},
+    };
+
+    async fn setup_test_db() -> SqlitePool {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        pool
+    }
+
+    async fn seed_test_db(pool: &SqlitePool) -> Vec<Redirect> {
+        let owner = User {
+            id: "some_id_string".to_owned(),
+            is_admin: false,
+            name: "testuser".to_owned(),
+            pwhash: "not_a_pw_hash".to_owned(),
+        };
+        sqlx::query("INSERT INTO users (id, name, pwhash, is_admin) VALUES ($1, $2, $3, $4);")
+            .bind(&owner.id)
+            .bind(&owner.name)
+            .bind(&owner.pwhash)
+            .bind(owner.is_admin)
+            .execute(pool)
+            .await
+            .unwrap();
+
+        let redirects = vec![
+            Redirect {
+                id: Uuid::new_v4().to_string(),
+                alias: "somealias".to_string(),
+                url: "https://someurl.com".to_string(),
+                owner: owner.id.clone(),
+                ..Default::default()
+            },
+            Redirect {
+                id: Uuid::new_v4().to_string(),
+                alias: "secondalias".to_string(),
+                url: "https://secondurl.com".to_string(),
+                owner: owner.id.clone(),
+                ..Default::default()
+            },
+        ];
+        for redirect in &redirects {
+            sqlx::query("INSERT INTO redirects (id, alias, url, owner) VALUES ($1, $2, $3, $4);")
+                .bind(&redirect.id)
+                .bind(&redirect.alias)
+                .bind(&redirect.url)
+                .bind(&redirect.owner)
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+        redirects
+    }
+
+    async fn read_tag_count(pool: &SqlitePool) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM tags;")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_add_tag_to_redirect_success() {
+        let pool = setup_test_db().await;
+        let repo = TagRepoSqliteImpl::new(pool.clone());
+        let redirects = seed_test_db(&pool).await;
+
+        let result = repo
+            .add_tag_to_redirect(&redirects[0].id, "project-x")
+            .await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), 1);
+
+        let tags = repo
+            .read_tags_by_redirect_ids(&[redirects[0].id.clone()])
+            .await
+            .unwrap();
+        assert_eq!(
+            tags,
+            vec![RedirectTag {
+                redirect_id: redirects[0].id.clone(),
+                tag: "project-x".to_owned(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_add_tag_twice_is_ignored() {
+        let pool = setup_test_db().await;
+        let repo = TagRepoSqliteImpl::new(pool.clone());
+        let redirects = seed_test_db(&pool).await;
+
+        repo.add_tag_to_redirect(&redirects[0].id, "project-x")
+            .await
+            .unwrap();
+        let result = repo
+            .add_tag_to_redirect(&redirects[0].id, "project-x")
+            .await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), 0);
+        assert_eq!(read_tag_count(&pool).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_add_tag_to_unknown_redirect_fails() {
+        let pool = setup_test_db().await;
+        let repo = TagRepoSqliteImpl::new(pool.clone());
+        seed_test_db(&pool).await;
+
+        let result = repo.add_tag_to_redirect("unknown_id", "project-x").await;
+        assert!(result.is_err());
+        assert_eq!(read_tag_count(&pool).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_remove_tag_keeps_tag_used_by_other_redirects() {
+        let pool = setup_test_db().await;
+        let repo = TagRepoSqliteImpl::new(pool.clone());
+        let redirects = seed_test_db(&pool).await;
+        for redirect in &redirects {
+            repo.add_tag_to_redirect(&redirect.id, "project-x")
+                .await
+                .unwrap();
+        }
+
+        let result = repo
+            .remove_tag_from_redirect(&redirects[0].id, "project-x")
+            .await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), 1);
+        assert_eq!(read_tag_count(&pool).await, 1);
+
+        repo.remove_tag_from_redirect(&redirects[1].id, "project-x")
+            .await
+            .unwrap();
+        assert_eq!(read_tag_count(&pool).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_remove_unknown_tag_leads_to_no_deletes() {
+        let pool = setup_test_db().await;
+        let repo = TagRepoSqliteImpl::new(pool.clone());
+        let redirects = seed_test_db(&pool).await;
+
+        let result = repo
+            .remove_tag_from_redirect(&redirects[0].id, "unknown")
+            .await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_read_tags_by_redirect_ids_returns_tags_of_all_redirects() {
+        let pool = setup_test_db().await;
+        let repo = TagRepoSqliteImpl::new(pool.clone());
+        let redirects = seed_test_db(&pool).await;
+        repo.add_tag_to_redirect(&redirects[0].id, "b-tag")
+            .await
+            .unwrap();
+        repo.add_tag_to_redirect(&redirects[0].id, "a-tag")
+            .await
+            .unwrap();
+        repo.add_tag_to_redirect(&redirects[1].id, "a-tag")
+            .await
+            .unwrap();
+
+        let ids: Vec<String> = redirects.iter().map(|r| r.id.clone()).collect();
+        let result = repo.read_tags_by_redirect_ids(&ids).await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        let tags = result.unwrap();
+        assert_eq!(tags.len(), 3);
+        assert_eq!(tags[2].tag, "b-tag");
+
+        let empty = repo.read_tags_by_redirect_ids(&[]).await.unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deleting_redirect_removes_its_tags() {
+        let pool = setup_test_db().await;
+        let repo = TagRepoSqliteImpl::new(pool.clone());
+        let redirects = seed_test_db(&pool).await;
+        repo.add_tag_to_redirect(&redirects[0].id, "project-x")
+            .await
+            .unwrap();
+
+        sqlx::query("DELETE FROM redirects WHERE id = $1;")
+            .bind(&redirects[0].id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let tags = repo
+            .read_tags_by_redirect_ids(&[redirects[0].id.clone()])
+            .await
+            .unwrap();
+        assert!(tags.is_empty());
+    }
+}
@@ -1,41 +1,125 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
 use sqlx::{Pool, Sqlite};
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
 
 use crate::{
     data::{DeletedResources, UserRepo, UserRepoError},
-    model::User,
+    model::{TrashedUserDTO, User},
+    now_unix,
 };
 
 pub struct UserRepoSqliteImpl {
     db: Pool<Sqlite>,
+    cancel_token: Option<CancellationToken>,
+}
+
+impl Drop for UserRepoSqliteImpl {
+    fn drop(&mut self) {
+        if let Some(token) = &self.cancel_token {
+            token.cancel();
+        }
+    }
 }
 
 impl UserRepoSqliteImpl {
     pub fn new(db: Pool<Sqlite>) -> Self {
-        UserRepoSqliteImpl { db }
+        UserRepoSqliteImpl {
+            db,
+            cancel_token: None,
+        }
+    }
+
+    pub fn with_cleanup(db: Pool<Sqlite>, interval: Duration, trash_retention: Duration) -> Self {
+        let mut repo = Self::new(db);
+        let cancel_token = CancellationToken::new();
+        tokio::spawn(Self::cleanup_task(
+            repo.db.clone(),
+            cancel_token.clone(),
+            interval,
+            trash_retention,
+        ));
+        repo.cancel_token = Some(cancel_token);
+        repo
+    }
+
+    async fn cleanup_task(
+        db: Pool<Sqlite>,
+        cancel_token: CancellationToken,
+        interval: Duration,
+        trash_retention: Duration,
+    ) {
+        let start = Instant::now() + interval;
+        let mut timer = tokio::time::interval_at(start, interval);
+        timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = timer.tick() => {
+                    let now = now_unix();
+                    let retention = i64::try_from(trash_retention.as_secs()).unwrap_or(i64::MAX);
+                    let purge_before = now.saturating_sub(retention);
+                    if let Err(e) = Self::purge_deleted_users_inner(&db, purge_before).await {
+                        eprintln!("Failed to purge deleted users: {e}");
+                    }
+                }
+                () = cancel_token.cancelled() => {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    async fn purge_deleted_users(&self, before: i64) -> Result<u64, sqlx::Error> {
+        Self::purge_deleted_users_inner(&self.db, before).await
+    }
+
+    async fn purge_deleted_users_inner(db: &Pool<Sqlite>, before: i64) -> Result<u64, sqlx::Error> {
+        let mut tx = db.begin().await?;
+        sqlx::query(
+            "DELETE FROM redirects
+            WHERE owner IN (SELECT id FROM users WHERE deleted_at <= $1);",
+        )
+        .bind(before)
+        .execute(&mut *tx)
+        .await?;
+        let result = sqlx::query("DELETE FROM users WHERE deleted_at <= $1;")
+            .bind(before)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(result.rows_affected())
     }
 }
 
 #[async_trait]
 impl UserRepo for UserRepoSqliteImpl {
     async fn read_user_by_name(&self, name: &str) -> Result<User, sqlx::Error> {
-        sqlx::query_as::<_, User>("SELECT id, name, pwhash, is_admin FROM users WHERE name = $1;")
-            .bind(name)
-            .fetch_one(&self.db)
-            .await
+        sqlx::query_as::<_, User>(
+            "SELECT id, name, pwhash, is_admin FROM users WHERE name = $1 AND deleted_at IS NULL;",
+        )
+        .bind(name)
+        .fetch_one(&self.db)
+        .await
     }
 
     async fn read_user_by_id(&self, id: &str) -> Result<User, sqlx::Error> {
-        sqlx::query_as::<_, User>("SELECT id, name, pwhash, is_admin FROM users WHERE id = $1;")
-            .bind(id)
-            .fetch_one(&self.db)
-            .await
+        sqlx::query_as::<_, User>(
+            "SELECT id, name, pwhash, is_admin FROM users WHERE id = $1 AND deleted_at IS NULL;",
+        )
+        .bind(id)
+        .fetch_one(&self.db)
+        .await
     }
 
     async fn read_users(&self) -> Result<Vec<User>, sqlx::Error> {
-        sqlx::query_as::<_, User>("SELECT id, name, pwhash, is_admin FROM users;")
-            .fetch_all(&self.db)
-            .await
+        sqlx::query_as::<_, User>(
+            "SELECT id, name, pwhash, is_admin FROM users WHERE deleted_at IS NULL;",
+        )
+        .fetch_all(&self.db)
+        .await
     }
 
     async fn create_user(&self, user: &User) -> Result<User, sqlx::Error> {
@@ -57,7 +141,7 @@ impl UserRepo for UserRepoSqliteImpl {
     }
 
     async fn update_user_by_id(&self, user: &User) -> Result<u64, sqlx::Error> {
-        let res = sqlx::query("UPDATE users SET name=$2, is_admin=$3, pwhash = $4 WHERE id=$1;")
+        let res = sqlx::query("UPDATE users SET name=$2, is_admin=$3, pwhash = $4 WHERE id=$1 AND deleted_at IS NULL;")
             .bind(&user.id)
             .bind(&user.name)
             .bind(user.is_admin)
@@ -69,24 +153,31 @@ impl UserRepo for UserRepoSqliteImpl {
     }
 
     async fn delete_user_by_id(&self, user_id: &str) -> Result<DeletedResources, UserRepoError> {
+        let now = now_unix();
         let mut tx = self.db.begin().await?;
-        let is_admin: bool = sqlx::query_scalar("SELECT is_admin FROM users where id = $1;")
-            .bind(user_id)
-            .fetch_one(&mut *tx)
-            .await?;
+        let is_admin: bool =
+            sqlx::query_scalar("SELECT is_admin FROM users where id = $1 AND deleted_at IS NULL;")
+                .bind(user_id)
+                .fetch_one(&mut *tx)
+                .await?;
 
         if is_admin {
             return Err(UserRepoError::IsAdmin);
         }
 
-        let deleted_redirects = sqlx::query("DELETE FROM redirects WHERE owner = $1")
-            .bind(user_id)
-            .execute(&mut *tx)
-            .await?
-            .rows_affected();
-
-        let deleted_users = sqlx::query("DELETE FROM users WHERE id = $1")
+        // the shared timestamp tells the redirects trashed with the user apart on restore
+        let deleted_redirects = sqlx::query(
+            "UPDATE redirects SET deleted_at = $2 WHERE owner = $1 AND deleted_at IS NULL",
+        )
+        .bind(user_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        let deleted_users = sqlx::query("UPDATE users SET deleted_at = $2 WHERE id = $1")
             .bind(user_id)
+            .bind(now)
             .execute(&mut *tx)
             .await?
             .rows_affected();
@@ -97,6 +188,42 @@ impl UserRepo for UserRepoSqliteImpl {
             affected_resources: deleted_redirects,
         })
     }
+
+    async fn read_deleted_users(&self) -> Result<Vec<TrashedUserDTO>, sqlx::Error> {
+        sqlx::query_as::<_, TrashedUserDTO>(
+            "SELECT id, name, deleted_at FROM users WHERE deleted_at IS NOT NULL
+            ORDER BY deleted_at DESC, id;",
+        )
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn restore_user_by_id(&self, user_id: &str) -> Result<u64, sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+        let deleted_at: i64 = sqlx::query_scalar(
+            "SELECT deleted_at FROM users WHERE id = $1 AND deleted_at IS NOT NULL;",
+        )
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE users SET deleted_at = NULL WHERE id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let restored_redirects = sqlx::query(
+            "UPDATE redirects SET deleted_at = NULL WHERE owner = $1 AND deleted_at = $2",
+        )
+        .bind(user_id)
+        .bind(deleted_at)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+        Ok(restored_redirects)
+    }
 }
 
 #[cfg(test)]
@@ -173,7 +300,7 @@ mod tests {
     }
 
     async fn read_redirects_from_test_db(pool: &SqlitePool) -> Vec<Redirect> {
-        sqlx::query_as::<_, Redirect>("SELECT * FROM redirects;")
+        sqlx::query_as::<_, Redirect>("SELECT * FROM redirects WHERE deleted_at IS NULL;")
             .fetch_all(pool)
             .await
             .unwrap()
@@ -336,18 +463,21 @@ mod tests {
                 alias: "somealias".to_owned(),
                 url: "someurl".to_owned(),
                 owner: users[0].clone().id,
+                ..Default::default()
             },
             Redirect {
                 id: Uuid::new_v4().to_string(),
                 alias: "somesecondalias".to_owned(),
                 url: "somesecondurl".to_owned(),
                 owner: users[0].clone().id,
+                ..Default::default()
             },
             Redirect {
                 id: Uuid::new_v4().to_string(),
                 alias: "someotheralias".to_owned(),
                 url: "someotherurl".to_owned(),
                 owner: users[1].clone().id,
+                ..Default::default()
             },
         ];
         for red in &redirects {
@@ -387,18 +517,21 @@ mod tests {
                 alias: "somealias".to_owned(),
                 url: "someurl".to_owned(),
                 owner: admin.clone().id,
+                ..Default::default()
             },
             Redirect {
                 id: Uuid::new_v4().to_string(),
                 alias: "somesecondalias".to_owned(),
                 url: "somesecondurl".to_owned(),
                 owner: users[0].clone().id,
+                ..Default::default()
             },
             Redirect {
                 id: Uuid::new_v4().to_string(),
                 alias: "someotheralias".to_owned(),
                 url: "someotherurl".to_owned(),
                 owner: users[1].clone().id,
+                ..Default::default()
             },
         ];
         for red in &redirects {
@@ -414,4 +547,87 @@ mod tests {
         assert_eq!(updated_redirects_from_db.len(), 3);
         assert_eq!(redirects, updated_redirects_from_db);
     }
+
+    #[tokio::test]
+    async fn test_restore_user_restores_redirects_deleted_with_user() {
+        let pool = setup_test_db().await;
+        let repo = UserRepoSqliteImpl::new(pool.clone());
+
+        let users = seed_test_db(&pool).await;
+        let redirects = vec![
+            Redirect {
+                id: Uuid::new_v4().to_string(),
+                alias: "somealias".to_owned(),
+                url: "someurl".to_owned(),
+                owner: users[0].clone().id,
+                ..Default::default()
+            },
+            Redirect {
+                id: Uuid::new_v4().to_string(),
+                alias: "somesecondalias".to_owned(),
+                url: "somesecondurl".to_owned(),
+                owner: users[0].clone().id,
+                ..Default::default()
+            },
+        ];
+        for red in &redirects {
+            insert_redirect_into_test_db(red, &pool).await;
+        }
+        // deleted on its own before the user, so it has to stay in the trash
+        sqlx::query("UPDATE redirects SET deleted_at = 1 WHERE id = $1;")
+            .bind(&redirects[1].id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let res = repo.delete_user_by_id(&users[0].id).await.unwrap();
+        assert_eq!(res.affected_resources, 1);
+        assert!(matches!(
+            repo.read_user_by_id(&users[0].id).await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+        let trash = repo.read_deleted_users().await.unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].id, users[0].id);
+
+        let result = repo.restore_user_by_id(&users[0].id).await;
+        dbg!(result.as_ref().err());
+        assert_eq!(result.unwrap(), 1);
+        assert!(repo.read_user_by_id(&users[0].id).await.is_ok());
+        assert_eq!(
+            read_redirects_from_test_db(&pool).await,
+            vec![redirects[0].clone()]
+        );
+        assert!(matches!(
+            repo.restore_user_by_id(&users[0].id).await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_purge_deleted_users_removes_user_and_redirects() {
+        let pool = setup_test_db().await;
+        let repo = UserRepoSqliteImpl::new(pool.clone());
+
+        let users = seed_test_db(&pool).await;
+        let redirect = Redirect {
+            id: Uuid::new_v4().to_string(),
+            alias: "somealias".to_owned(),
+            url: "someurl".to_owned(),
+            owner: users[0].clone().id,
+            ..Default::default()
+        };
+        insert_redirect_into_test_db(&redirect, &pool).await;
+        repo.delete_user_by_id(&users[0].id).await.unwrap();
+
+        assert_eq!(repo.purge_deleted_users(0).await.unwrap(), 0);
+        assert_eq!(repo.purge_deleted_users(i64::MAX).await.unwrap(), 1);
+        assert!(read_from_test_db(&users[0].id, &pool).await.is_err());
+        let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM redirects;")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(remaining, 0);
+        assert!(repo.create_user(&users[0]).await.is_ok());
+    }
 }
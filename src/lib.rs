@@ -10,7 +10,14 @@
 #![deny(clippy::unimplemented)]
 #![cfg_attr(test, allow(clippy::unwrap_used))]
 
-use std::{env, error::Error, fs::read_to_string, net::SocketAddr, sync::Arc, time::Duration};
+use std::{
+    env,
+    error::Error,
+    fs::read_to_string,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use axum::{Router, routing::get};
 use metrics_exporter_prometheus::PrometheusHandle;
@@ -19,10 +26,14 @@ use tokio::signal;
 
 use crate::{
     controller::{admin, health_check, login, redirect, user},
-    data::{RedirectRepoSqliteImpl, UserRegistrationTokenInMemoryImpl, UserRepoSqliteImpl},
+    data::{
+        ClickRepoSqliteImpl, RedirectRepoSqliteImpl, TagRepoSqliteImpl,
+        UserRegistrationTokenInMemoryImpl, UserRepoSqliteImpl,
+    },
     service::{
-        LoginService, LoginServiceImpl, RedirectService, RedirectServiceImpl, UserService,
-        UserServiceImpl,
+        AnalyticsService, AnalyticsServiceImpl, ClickService, ClickServiceImpl, ExportService,
+        ExportServiceImpl, LoginService, LoginServiceImpl, RedirectService, RedirectServiceImpl,
+        TagService, TagServiceImpl, UserService, UserServiceImpl,
     },
 };
 
@@ -40,6 +51,10 @@ struct AppContext {
     redirect_service: Arc<dyn RedirectService + Send + Sync>,
     login_service: Arc<dyn LoginService + Send + Sync>,
     user_service: Arc<dyn UserService + Send + Sync>,
+    click_service: Arc<dyn ClickService + Send + Sync>,
+    analytics_service: Arc<dyn AnalyticsService + Send + Sync>,
+    tag_service: Arc<dyn TagService + Send + Sync>,
+    export_service: Arc<dyn ExportService + Send + Sync>,
     metrics: PrometheusHandle,
 }
 #[derive(Clone)]
@@ -48,6 +63,23 @@ struct AppConfig {
     db_location: String,
     jwt_config: JwtConfig,
     reg_token_ttl: u64,
+    trash_retention: u64,
+    alias_config: AliasConfig,
+    fallback: Fallback,
+    trust_forwarded_for: bool,
+}
+#[derive(Clone, Default)]
+enum Fallback {
+    #[default]
+    Status,
+    Redirect(String),
+    Page(Arc<str>),
+}
+#[derive(Clone)]
+struct AliasConfig {
+    length: usize,
+    alphabet: Vec<char>,
+    case_insensitive: bool,
 }
 #[derive(Clone)]
 struct JwtConfig {
@@ -56,13 +88,42 @@ struct JwtConfig {
     ttl: u64,
 }
 
+pub(crate) fn now_unix() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system clock is before Unix epoch")
+        .as_secs()
+        .try_into()
+        .expect("timestamp overflow")
+}
+
 fn create_app_context(pool: &Pool<Sqlite>, app_config: AppConfig) -> AppContext {
-    let redirect_repo = Arc::new(RedirectRepoSqliteImpl::new(pool.clone()));
-    let user_repo = Arc::new(UserRepoSqliteImpl::new(pool.clone()));
+    let trash_retention = Duration::from_secs(app_config.trash_retention);
+    let redirect_repo = Arc::new(RedirectRepoSqliteImpl::with_cleanup(
+        pool.clone(),
+        Duration::from_hours(1),
+        trash_retention,
+    ));
+    let click_repo = Arc::new(ClickRepoSqliteImpl::new(pool.clone()));
+    let tag_repo = Arc::new(TagRepoSqliteImpl::new(pool.clone()));
+    let user_repo = Arc::new(UserRepoSqliteImpl::with_cleanup(
+        pool.clone(),
+        Duration::from_hours(1),
+        trash_retention,
+    ));
     let user_registration_token_repo = Arc::new(UserRegistrationTokenInMemoryImpl::with_cleanup(
         Duration::from_hours(1),
     ));
-    let redirect_service = RedirectServiceImpl::new(redirect_repo);
+    let redirect_service = RedirectServiceImpl::new(
+        redirect_repo.clone(),
+        click_repo.clone(),
+        tag_repo.clone(),
+        app_config.alias_config.clone(),
+    );
+    let tag_service = TagServiceImpl::new(redirect_repo.clone(), tag_repo.clone());
+    let export_service = ExportServiceImpl::new(redirect_repo.clone(), tag_repo);
+    let analytics_service = AnalyticsServiceImpl::new(redirect_repo, click_repo.clone());
+    let click_service = ClickServiceImpl::new(click_repo, 500, Duration::from_secs(5));
     let user_service = UserServiceImpl::new(user_repo.clone(), user_registration_token_repo);
     let login_service = LoginServiceImpl::new(user_repo);
     let metrics = telemetry::init_metrics();
@@ -71,6 +132,10 @@ fn create_app_context(pool: &Pool<Sqlite>, app_config: AppConfig) -> AppContext
         redirect_service: Arc::new(redirect_service),
         login_service: Arc::new(login_service),
         user_service: Arc::new(user_service),
+        click_service: Arc::new(click_service),
+        analytics_service: Arc::new(analytics_service),
+        tag_service: Arc::new(tag_service),
+        export_service: Arc::new(export_service),
         metrics,
     }
 }
@@ -79,12 +144,36 @@ fn read_secret(name: &str) -> Result<String, std::io::Error> {
     read_to_string(format!("/run/secrets/{name}")).map(|s| s.trim().to_string())
 }
 
+const FALLBACK_URL: &str = "VIA_ALIAS_FALLBACK_URL";
+const FALLBACK_PAGE: &str = "VIA_ALIAS_FALLBACK_PAGE";
+
+fn parse_fallback(url: Option<String>, page: Option<String>) -> Result<Fallback, String> {
+    match (url, page) {
+        (Some(_), Some(_)) => Err(format!(
+            "only one of {FALLBACK_URL} and {FALLBACK_PAGE} can be set"
+        )),
+        (Some(url), None) if url.starts_with("http://") || url.starts_with("https://") => {
+            Ok(Fallback::Redirect(url))
+        }
+        (Some(_), None) => Err(format!("{FALLBACK_URL} has to be a http or https url")),
+        (None, Some(path)) => read_to_string(&path)
+            .map(|page| Fallback::Page(page.into()))
+            .map_err(|e| format!("{FALLBACK_PAGE} could not be read: {e}")),
+        (None, None) => Ok(Fallback::Status),
+    }
+}
+
 fn generate_app_config() -> Result<AppConfig, Box<dyn Error>> {
     const JWT_SECRET_ENV: &str = "VIA_ALIAS_JWT_SECRET";
     const JWT_TTL: &str = "VIA_ALIAS_JWT_TTL";
     const PORT_ENV: &str = "VIA_ALIAS_PORT";
     const DB_LOC_ENV: &str = "VIA_ALIAS_DB";
     const REG_TOKEN_TTL: &str = "VIA_ALIAS_REG_TOKEN_TTL";
+    const TRASH_RETENTION: &str = "VIA_ALIAS_TRASH_RETENTION";
+    const ALIAS_LENGTH: &str = "VIA_ALIAS_ALIAS_LENGTH";
+    const ALIAS_ALPHABET: &str = "VIA_ALIAS_ALIAS_ALPHABET";
+    const ALIAS_CASE_INSENSITIVE: &str = "VIA_ALIAS_ALIAS_CASE_INSENSITIVE";
+    const TRUST_FORWARDED_FOR: &str = "VIA_ALIAS_TRUST_FORWARDED_FOR";
     let secret = read_secret(JWT_SECRET_ENV)
         .or_else(|_| env::var(JWT_SECRET_ENV))
         .map_err(|_| format!("{JWT_SECRET_ENV} is not set"))?;
@@ -109,6 +198,55 @@ fn generate_app_config() -> Result<AppConfig, Box<dyn Error>> {
         .parse()
         .map_err(|_| format!("{REG_TOKEN_TTL} is not a valid value"))?;
 
+    let trash_retention: u64 = env::var(TRASH_RETENTION)
+        .unwrap_or_else(|_| "2592000".to_owned())
+        .parse()
+        .map_err(|_| format!("{TRASH_RETENTION} is not a valid value"))?;
+
+    let alias_length: usize = env::var(ALIAS_LENGTH)
+        .unwrap_or_else(|_| "6".to_owned())
+        .parse()
+        .ok()
+        .filter(|l| (1..=50).contains(l))
+        .ok_or_else(|| format!("{ALIAS_LENGTH} has to be a number between 1 and 50"))?;
+
+    let mut alias_alphabet: Vec<char> = env::var(ALIAS_ALPHABET)
+        .unwrap_or_else(|_| {
+            "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz".to_owned()
+        })
+        .chars()
+        .collect();
+    alias_alphabet.sort_unstable();
+    alias_alphabet.dedup();
+    if alias_alphabet.len() < 2
+        || !alias_alphabet
+            .iter()
+            .all(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+    {
+        return Err(format!(
+            "{ALIAS_ALPHABET} has to contain at least two distinct characters out of a-z, A-Z, 0-9, - and _"
+        )
+        .into());
+    }
+
+    let alias_case_insensitive: bool = env::var(ALIAS_CASE_INSENSITIVE)
+        .unwrap_or_else(|_| "false".to_owned())
+        .parse()
+        .map_err(|_| format!("{ALIAS_CASE_INSENSITIVE} has to be true or false"))?;
+
+    let alias_config = AliasConfig {
+        length: alias_length,
+        alphabet: alias_alphabet,
+        case_insensitive: alias_case_insensitive,
+    };
+
+    let fallback = parse_fallback(env::var(FALLBACK_URL).ok(), env::var(FALLBACK_PAGE).ok())?;
+
+    let trust_forwarded_for: bool = env::var(TRUST_FORWARDED_FOR)
+        .unwrap_or_else(|_| "false".to_owned())
+        .parse()
+        .map_err(|_| format!("{TRUST_FORWARDED_FOR} has to be true or false"))?;
+
     let jwt_config = JwtConfig {
         secret,
         alg: jsonwebtoken::Algorithm::HS512,
@@ -120,6 +258,10 @@ fn generate_app_config() -> Result<AppConfig, Box<dyn Error>> {
         db_location,
         jwt_config,
         reg_token_ttl,
+        trash_retention,
+        alias_config,
+        fallback,
+        trust_forwarded_for,
     })
 }
 
@@ -134,7 +276,15 @@ fn create_router(context: AppContext) -> Router {
         ))
         .merge(user::user_router())
         .merge(login::router())
-        .route("/{alias}", get(redirect::follow_redirect_handler))
+        .route(
+            "/{alias}",
+            get(redirect::follow_redirect_handler).post(redirect::unlock_redirect_handler),
+        )
+        .route(
+            "/{alias}/{*path}",
+            get(redirect::follow_redirect_with_path_handler)
+                .post(redirect::unlock_redirect_with_path_handler),
+        )
         .route("/metrics", get(controller::metrics::metrics_handler))
         .with_state(context)
         .merge(api_doc::api_doc_router())
@@ -154,18 +304,39 @@ pub async fn run_app() -> Result<(), Box<dyn Error>> {
 
     let app_state = create_app_context(&pool, app_config);
     app_state.user_service.create_admin_first_start().await?;
+    let collisions = app_state
+        .redirect_service
+        .resolve_alias_collisions()
+        .await?;
+    if !collisions.is_empty() {
+        println!("----------------------------------------");
+        println!(
+            "The following aliases only differ in case and can't be resolved case-insensitively."
+        );
+        println!("Rename or delete all but one of them:");
+        for collision in &collisions {
+            println!("{:<20} {}", collision.alias_key, collision.alias);
+        }
+        println!("----------------------------------------");
+    }
 
     let port = app_state.app_config.port;
+    let click_service = app_state.click_service.clone();
     let app = create_router(app_state);
 
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     let listener = tokio::net::TcpListener::bind(&addr).await?;
     println!("Listening on port {port}...");
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await?;
 
+    println!("Flushing recorded clicks");
+    click_service.shutdown().await;
     println!("Closing database connection");
     pool.close().await;
     println!("Terminating...");
@@ -193,3 +364,40 @@ async fn shutdown_signal() {
         () = terminate => {println!("Received termination signal")},
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{Fallback, parse_fallback};
+
+    #[test]
+    fn parse_fallback_accepts_one_of_url_and_page() {
+        assert!(matches!(parse_fallback(None, None), Ok(Fallback::Status)));
+        assert!(matches!(
+            parse_fallback(Some("https://example.com".to_owned()), None),
+            Ok(Fallback::Redirect(url)) if url == "https://example.com"
+        ));
+
+        let path =
+            std::env::temp_dir().join(format!("via-alias-fallback-{}.html", uuid::Uuid::new_v4()));
+        fs::write(&path, "<h1>Not here</h1>").unwrap();
+        let page = parse_fallback(None, Some(path.to_string_lossy().into_owned()));
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(page, Ok(Fallback::Page(page)) if &*page == "<h1>Not here</h1>"));
+    }
+
+    #[test]
+    fn parse_fallback_rejects_invalid_values() {
+        assert!(
+            parse_fallback(
+                Some("https://example.com".to_owned()),
+                Some("fallback.html".to_owned())
+            )
+            .is_err()
+        );
+        assert!(parse_fallback(Some("ftp://example.com".to_owned()), None).is_err());
+        assert!(parse_fallback(Some("example.com".to_owned()), None).is_err());
+        assert!(parse_fallback(None, Some("/does/not/exist.html".to_owned())).is_err());
+    }
+}
@@ -21,6 +21,17 @@ pub(crate) async fn auth_middleware(
         return StatusCode::UNAUTHORIZED.into_response();
     };
 
+    // tokens outlive their user, deleted users must not be able to use them
+    match app_context
+        .user_service
+        .get_simple_user_info(&token_data.claims.user_id)
+        .await
+    {
+        Ok(_) => {}
+        Err(DbServiceError::NotFoundError) => return StatusCode::UNAUTHORIZED.into_response(),
+        Err(e) => return e.into_response(),
+    }
+
     request.extensions_mut().insert(token_data.claims);
 
     next.run(request).await
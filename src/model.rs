@@ -1,5 +1,9 @@
+mod click;
 mod redirect;
+mod tag;
 mod user;
 
+pub(crate) use self::click::*;
 pub(crate) use self::redirect::*;
+pub(crate) use self::tag::*;
 pub(crate) use self::user::*;
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::now_unix;
+
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub(crate) struct ClickEvent {
+    pub redirect_id: String,
+    pub clicked_at: i64,
+    pub referrer: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl ClickEvent {
+    pub fn new(redirect_id: String, referrer: Option<String>, user_agent: Option<String>) -> Self {
+        let clicked_at = now_unix();
+        Self {
+            redirect_id,
+            clicked_at,
+            referrer,
+            user_agent,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(title = "RedirectStats")]
+pub(crate) struct RedirectStatsDTO {
+    #[schema(examples("gh"))]
+    pub alias: String,
+    #[schema(examples(42))]
+    pub clicks: i64,
+    /// Unix timestamp of the most recent recorded click.
+    #[schema(examples(1772897393))]
+    pub last_clicked_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Granularity {
+    Hour,
+    #[default]
+    Day,
+}
+
+impl Granularity {
+    pub fn bucket_size(self) -> i64 {
+        match self {
+            Granularity::Hour => 3600,
+            Granularity::Day => 86400,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct AnalyticsQueryDTO {
+    /// Start of the range as unix timestamp. Defaults to 24 buckets before `to`.
+    #[param(example = 1772236800)]
+    pub from: Option<i64>,
+    /// End of the range as unix timestamp. Defaults to now.
+    #[param(example = 1772841600)]
+    pub to: Option<i64>,
+    /// Size of the buckets. Defaults to `day`.
+    pub granularity: Option<Granularity>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, sqlx::FromRow, ToSchema)]
+#[schema(title = "ClickBucket")]
+pub(crate) struct ClickBucketDTO {
+    #[schema(examples(1772841600))]
+    pub bucket_start: i64,
+    #[schema(examples(42))]
+    pub clicks: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(title = "ClickHistogram")]
+pub(crate) struct ClickHistogramDTO {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(examples("gh"))]
+    pub alias: Option<String>,
+    pub granularity: Granularity,
+    #[schema(examples(1772236800))]
+    pub from: i64,
+    #[schema(examples(1772841600))]
+    pub to: i64,
+    pub buckets: Vec<ClickBucketDTO>,
+}
@@ -1,5 +1,77 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{
+    IntoParams, PartialSchema, ToSchema,
+    openapi::{ObjectBuilder, RefOr, Schema, Type},
+};
+
+use crate::service::{DbServiceError, ValidationErrorResponse};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "u16", into = "u16")]
+pub(crate) enum RedirectType {
+    MovedPermanently,
+    Found,
+    #[default]
+    TemporaryRedirect,
+    PermanentRedirect,
+}
+
+impl TryFrom<u16> for RedirectType {
+    type Error = DbServiceError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            301 => Ok(Self::MovedPermanently),
+            302 => Ok(Self::Found),
+            307 => Ok(Self::TemporaryRedirect),
+            308 => Ok(Self::PermanentRedirect),
+            _ => Err(DbServiceError::PayloadValidationError(
+                "redirect_type".to_owned(),
+                vec!["has to be one of 301, 302, 307 or 308".to_owned()],
+            )),
+        }
+    }
+}
+
+impl From<RedirectType> for u16 {
+    fn from(value: RedirectType) -> Self {
+        match value {
+            RedirectType::MovedPermanently => 301,
+            RedirectType::Found => 302,
+            RedirectType::TemporaryRedirect => 307,
+            RedirectType::PermanentRedirect => 308,
+        }
+    }
+}
+
+impl PartialSchema for RedirectType {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .schema_type(Type::Integer)
+            .enum_values(Some([301, 302, 307, 308]))
+            .default(Some(307.into()))
+            .examples([308])
+            .into()
+    }
+}
+
+impl ToSchema for RedirectType {}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RedirectState {
+    #[default]
+    Active,
+    Deleted,
+    Disabled,
+    Scheduled,
+    Ended,
+    Expired,
+    Exhausted,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, sqlx::FromRow, PartialEq, ToSchema)]
 #[schema(title = "RedirectData")]
 pub(crate) struct Redirect {
@@ -11,21 +83,288 @@ pub(crate) struct Redirect {
     pub url: String,
     #[schema(examples("7484bf63-0c9a-41af-884e-e0fea7f0bb8e"))]
     pub owner: String,
+    #[schema(examples(42))]
+    pub clicks: i64,
+    #[schema(examples(1772897393))]
+    pub expires_at: Option<i64>,
+    #[schema(examples(10))]
+    pub max_clicks: Option<i64>,
+    #[schema(examples(3))]
+    pub remaining_clicks: Option<i64>,
+    #[sqlx(try_from = "u16")]
+    pub redirect_type: RedirectType,
+    #[schema(examples(false))]
+    pub passthrough: bool,
+    #[schema(examples(false))]
+    pub template: bool,
+    #[sqlx(json)]
+    #[schema(examples(json!({"utm_source": "newsletter"})))]
+    pub query_params: BTreeMap<String, String>,
+    #[schema(examples(true))]
+    pub case_insensitive: Option<bool>,
+    #[schema(examples(false))]
+    pub preview: bool,
+    #[serde(skip)]
+    #[schema(ignore)]
+    pub pwhash: Option<String>,
+    #[schema(examples(1772236800))]
+    pub active_from: Option<i64>,
+    #[schema(examples(1772841600))]
+    pub active_until: Option<i64>,
+    #[schema(examples("https://example.com/coming-soon"))]
+    pub fallback_url: Option<String>,
+    #[schema(examples(true))]
+    pub enabled: bool,
+    #[schema(examples(false))]
+    pub locked: bool,
+    #[schema(examples(1772841600))]
+    pub deleted_at: Option<i64>,
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub state: RedirectState,
+    #[schema(examples(1772236800))]
+    pub created_at: i64,
+    #[schema(examples(1772841600))]
+    pub updated_at: i64,
+    #[schema(examples("7484bf63-0c9a-41af-884e-e0fea7f0bb8e"))]
+    pub updated_by: Option<String>,
+    #[sqlx(skip)]
+    #[schema(examples(json!(["project-x"])))]
+    pub tags: Vec<String>,
+    #[sqlx(skip)]
+    #[schema(examples(json!(["github", "git"])))]
+    pub aliases: Vec<String>,
+}
+
+impl Default for Redirect {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            alias: String::new(),
+            url: String::new(),
+            owner: String::new(),
+            clicks: 0,
+            expires_at: None,
+            max_clicks: None,
+            remaining_clicks: None,
+            redirect_type: RedirectType::default(),
+            passthrough: false,
+            template: false,
+            query_params: BTreeMap::new(),
+            case_insensitive: None,
+            preview: false,
+            pwhash: None,
+            active_from: None,
+            active_until: None,
+            fallback_url: None,
+            enabled: true,
+            locked: false,
+            deleted_at: None,
+            state: RedirectState::default(),
+            created_at: 0,
+            updated_at: 0,
+            updated_by: None,
+            tags: Vec::new(),
+            aliases: Vec::new(),
+        }
+    }
+}
+
+impl Redirect {
+    pub(crate) fn state_at(&self, now: i64) -> RedirectState {
+        if self.deleted_at.is_some() {
+            RedirectState::Deleted
+        } else if !self.enabled {
+            RedirectState::Disabled
+        } else if self.expires_at.is_some_and(|exp| exp <= now) {
+            RedirectState::Expired
+        } else if self.remaining_clicks == Some(0) {
+            RedirectState::Exhausted
+        } else if self.active_from.is_some_and(|from| from > now) {
+            RedirectState::Scheduled
+        } else if self.active_until.is_some_and(|until| until <= now) {
+            RedirectState::Ended
+        } else {
+            RedirectState::Active
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, ToSchema)]
 #[schema(title = "FullRedirectList")]
 pub(crate) struct FullRedirectListDTO {
     pub redirects: Vec<Redirect>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(examples("YWxpYXN8YXNjfGQ2NGJjYWFkfGdo"))]
+    pub next_cursor: Option<String>,
 }
 
-#[derive(Deserialize, Serialize, sqlx::FromRow, Debug, Clone, PartialEq, ToSchema)]
+#[derive(Deserialize, Serialize, sqlx::FromRow, Debug, Clone, Default, PartialEq, ToSchema)]
 #[schema(title = "SimpleRedirectData")]
 pub(crate) struct RedirectDTO {
+    #[serde(default)]
     #[schema(examples("gh"))]
     pub alias: String,
     #[schema(examples("http://www.github.com"))]
     pub url: String,
+    #[serde(default)]
+    #[sqlx(default)]
+    #[schema(read_only, examples(42))]
+    pub clicks: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[sqlx(default)]
+    #[schema(examples(1772897393))]
+    pub expires_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[sqlx(default)]
+    #[schema(examples(10))]
+    pub max_clicks: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[sqlx(default)]
+    #[schema(read_only, examples(3))]
+    pub remaining_clicks: Option<i64>,
+    #[serde(default)]
+    #[sqlx(default, try_from = "u16")]
+    pub redirect_type: RedirectType,
+    /// Forward additional path segments and the query string to the target url.
+    #[serde(default)]
+    #[sqlx(default)]
+    #[schema(examples(false))]
+    pub passthrough: bool,
+    /// Treat the url as a template with placeholders like `{1}`, `{path}` or `{query}`.
+    #[serde(default)]
+    #[sqlx(default)]
+    #[schema(examples(false))]
+    pub template: bool,
+    /// Query parameters that are added to the url when the redirect is followed, unless the
+    /// url already contains a parameter with the same name.
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        deserialize_with = "query_params_from_map_or_json"
+    )]
+    #[sqlx(skip)]
+    #[schema(examples(json!({"utm_source": "newsletter", "utm_campaign": "spring"})))]
+    pub query_params: BTreeMap<String, String>,
+    /// Resolve the alias regardless of its case. Defaults to the server setting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[sqlx(default)]
+    #[schema(examples(true))]
+    pub case_insensitive: Option<bool>,
+    /// Show an interstitial page with the destination instead of redirecting directly.
+    #[serde(default)]
+    #[sqlx(default)]
+    #[schema(examples(false))]
+    pub preview: bool,
+    /// Passphrase that has to be entered before the redirect is followed.
+    #[serde(default, skip_serializing)]
+    #[sqlx(skip)]
+    #[schema(write_only, examples("correct horse battery staple"))]
+    pub password: Option<String>,
+    /// Whether a password has to be entered before the redirect is followed.
+    #[serde(default)]
+    #[sqlx(default)]
+    #[schema(read_only, examples(false))]
+    pub protected: bool,
+    /// Unix timestamp from which on the redirect can be followed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[sqlx(default)]
+    #[schema(examples(1772236800))]
+    pub active_from: Option<i64>,
+    /// Unix timestamp from which on the redirect can no longer be followed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[sqlx(default)]
+    #[schema(examples(1772841600))]
+    pub active_until: Option<i64>,
+    /// Url that is redirected to while the redirect is not active, has expired or reached its
+    /// click limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[sqlx(default)]
+    #[schema(examples("https://example.com/coming-soon"))]
+    pub fallback_url: Option<String>,
+    /// Disabled redirects can't be followed. Changed via the enable and disable endpoints, only
+    /// imports read it.
+    #[serde(default = "enabled_by_default")]
+    #[sqlx(default)]
+    #[schema(read_only, examples(true))]
+    pub enabled: bool,
+    /// Set when an admin disabled the redirect, only admins can enable it again.
+    #[serde(default, skip_deserializing)]
+    #[sqlx(default)]
+    #[schema(read_only, examples(false))]
+    pub locked: bool,
+    /// Unix timestamp the redirect was moved to the trash at.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    #[sqlx(default)]
+    #[schema(read_only, examples(1772841600))]
+    pub deleted_at: Option<i64>,
+    #[serde(default, skip_deserializing)]
+    #[sqlx(skip)]
+    #[schema(read_only)]
+    pub state: RedirectState,
+    #[serde(default)]
+    #[sqlx(default)]
+    #[schema(read_only, examples(1772236800))]
+    pub created_at: i64,
+    #[serde(default)]
+    #[sqlx(default)]
+    #[schema(read_only, examples(1772841600))]
+    pub updated_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[sqlx(default)]
+    #[schema(read_only, examples("7484bf63-0c9a-41af-884e-e0fea7f0bb8e"))]
+    pub updated_by: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "ignore_managed"
+    )]
+    #[sqlx(skip)]
+    #[schema(read_only, examples(json!(["project-x"])))]
+    pub tags: Vec<String>,
+    /// Secondary aliases that resolve to the same redirect.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "ignore_managed"
+    )]
+    #[sqlx(skip)]
+    #[schema(read_only, examples(json!(["github", "git"])))]
+    pub aliases: Vec<String>,
+}
+
+// tags and secondary aliases are managed via their own endpoints, so they are accepted but
+// ignored in payloads
+fn ignore_managed<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    serde::de::IgnoredAny::deserialize(deserializer)?;
+    Ok(Vec::new())
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+// CSV imports carry the query params as a JSON object in a single column
+fn query_params_from_map_or_json<'de, D>(
+    deserializer: D,
+) -> Result<BTreeMap<String, String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum QueryParams {
+        Map(BTreeMap<String, String>),
+        Json(String),
+    }
+    match QueryParams::deserialize(deserializer)? {
+        QueryParams::Map(params) => Ok(params),
+        QueryParams::Json(json) if json.is_empty() => Ok(BTreeMap::new()),
+        QueryParams::Json(json) => serde_json::from_str(&json).map_err(serde::de::Error::custom),
+    }
 }
 
 impl From<Redirect> for RedirectDTO {
@@ -33,6 +372,30 @@ impl From<Redirect> for RedirectDTO {
         Self {
             alias: value.alias,
             url: value.url,
+            clicks: value.clicks,
+            expires_at: value.expires_at,
+            max_clicks: value.max_clicks,
+            remaining_clicks: value.remaining_clicks,
+            redirect_type: value.redirect_type,
+            passthrough: value.passthrough,
+            template: value.template,
+            query_params: value.query_params,
+            case_insensitive: value.case_insensitive,
+            preview: value.preview,
+            password: None,
+            protected: value.pwhash.is_some(),
+            active_from: value.active_from,
+            active_until: value.active_until,
+            fallback_url: value.fallback_url,
+            enabled: value.enabled,
+            locked: value.locked,
+            deleted_at: value.deleted_at,
+            state: value.state,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            updated_by: value.updated_by,
+            tags: value.tags,
+            aliases: value.aliases,
         }
     }
 }
@@ -46,11 +409,317 @@ pub(crate) struct RedirectCreationDTO {
 #[schema(title = "RedirectList")]
 pub(crate) struct RedirectListDTO {
     pub redirects: Vec<RedirectDTO>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(examples("YWxpYXN8YXNjfGQ2NGJjYWFkfGdo"))]
+    pub next_cursor: Option<String>,
 }
 
-#[derive(Deserialize, ToSchema)]
-#[schema(title = "UpdateUrl")]
-pub(crate) struct UpdateUrlDTO {
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum RedirectSortKey {
+    #[default]
+    Alias,
+    Created,
+    Clicks,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct RedirectListQueryDTO {
+    /// Maximum number of redirects per page. Defaults to 100, at most 1000.
+    #[param(example = 100)]
+    pub limit: Option<i64>,
+    /// The `next_cursor` of the previous page. Has to be used with the same `sort` and `order`.
+    pub cursor: Option<String>,
+    /// Field to sort by. Defaults to `alias`.
+    pub sort: Option<RedirectSortKey>,
+    /// Sort order. Defaults to `asc`.
+    pub order: Option<SortOrder>,
+    /// Only return redirects whose alias or url contains this text.
+    #[param(example = "github")]
+    pub q: Option<String>,
+    /// Only return redirects with this tag.
+    #[param(example = "project-x")]
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum CursorValue {
+    Text(String),
+    Integer(i64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RedirectCursor {
+    pub value: CursorValue,
+    pub id: String,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct RedirectPageQuery {
+    pub limit: i64,
+    pub sort: RedirectSortKey,
+    pub order: SortOrder,
+    pub search: Option<String>,
+    pub tag: Option<String>,
+    pub after: Option<RedirectCursor>,
+    /// Reads the redirects in the trash instead of the active ones.
+    pub deleted: bool,
+}
+
+impl RedirectPageQuery {
+    pub const DEFAULT_LIMIT: i64 = 100;
+    pub const MAX_LIMIT: i64 = 1000;
+}
+
+impl Default for RedirectPageQuery {
+    fn default() -> Self {
+        Self {
+            limit: Self::DEFAULT_LIMIT,
+            sort: RedirectSortKey::default(),
+            order: SortOrder::default(),
+            search: None,
+            tag: None,
+            after: None,
+            deleted: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct RedirectPage {
+    pub redirects: Vec<Redirect>,
+    pub next_cursor: Option<RedirectCursor>,
+}
+
+// distinguishes an explicit null, Some(None), from a missing field, None
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[derive(Deserialize, Default, ToSchema)]
+#[schema(title = "UpdateRedirect")]
+pub(crate) struct UpdateRedirectDTO {
+    #[schema(examples("new-alias"))]
+    pub alias: Option<String>,
     #[schema(examples("http://my-new-redirect-url.de"))]
-    pub url: String,
+    pub url: Option<String>,
+    #[schema(examples("d64bcaad-8d86-48d2-b1f3-f1c03ac30fa3"))]
+    pub owner: Option<String>,
+    pub redirect_type: Option<RedirectType>,
+    #[schema(examples(true))]
+    pub passthrough: Option<bool>,
+    #[schema(examples(false))]
+    pub template: Option<bool>,
+    /// Replaces all query parameters of the redirect, an empty object removes them.
+    #[schema(examples(json!({"utm_source": "newsletter"})))]
+    pub query_params: Option<BTreeMap<String, String>>,
+    /// Overrides the server default, `null` resets it to the server default.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<bool>, examples(true))]
+    pub case_insensitive: Option<Option<bool>>,
+    #[schema(examples(false))]
+    pub preview: Option<bool>,
+    /// Sets the passphrase of the redirect, `null` removes it.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<String>, examples("correct horse battery staple"))]
+    pub password: Option<Option<String>>,
+    /// Sets the start of the activation window, `null` removes it.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<i64>, examples(1772236800))]
+    pub active_from: Option<Option<i64>>,
+    /// Sets the end of the activation window, `null` removes it.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<i64>, examples(1772841600))]
+    pub active_until: Option<Option<i64>>,
+    /// Sets the fallback url, `null` removes it.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<String>, examples("https://example.com/coming-soon"))]
+    pub fallback_url: Option<Option<String>>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[schema(title = "RedirectPassword")]
+pub(crate) struct RedirectPasswordDTO {
+    #[schema(examples("correct horse battery staple"))]
+    pub password: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, sqlx::FromRow, ToSchema)]
+#[schema(title = "RedirectHistoryEntry")]
+pub(crate) struct RedirectHistoryEntryDTO {
+    #[schema(examples(7))]
+    pub id: i64,
+    #[schema(examples("https://www.github.com"))]
+    pub old_url: String,
+    #[schema(examples("https://github.com"))]
+    pub new_url: String,
+    #[schema(examples("7484bf63-0c9a-41af-884e-e0fea7f0bb8e"))]
+    pub changed_by: Option<String>,
+    #[schema(examples(1772841600))]
+    pub changed_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[schema(title = "RedirectHistory")]
+pub(crate) struct RedirectHistoryDTO {
+    #[schema(examples("gh"))]
+    pub alias: String,
+    /// Url changes of the redirect, newest first.
+    pub history: Vec<RedirectHistoryEntryDTO>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub(crate) struct RedirectAlias {
+    pub redirect_id: String,
+    pub alias: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub(crate) struct AliasCollision {
+    pub alias_key: String,
+    pub alias: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ImportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct ImportQueryDTO {
+    /// Only validate the import and report the result without creating any redirects.
+    #[param(example = true)]
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ImportRowStatus {
+    Created,
+    Conflict,
+    Invalid,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(title = "ImportRowResult")]
+pub(crate) struct ImportRowResultDTO {
+    /// Position of the row in the import, starting at 1. The CSV header is not counted.
+    #[schema(examples(1))]
+    pub row: usize,
+    #[schema(examples("gh"))]
+    pub alias: String,
+    pub status: ImportRowStatus,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ValidationErrorResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(title = "ImportReport")]
+pub(crate) struct ImportReportDTO {
+    #[schema(examples(false))]
+    pub dry_run: bool,
+    #[schema(examples(1))]
+    pub created: usize,
+    #[schema(examples(0))]
+    pub conflicts: usize,
+    #[schema(examples(0))]
+    pub invalid: usize,
+    pub rows: Vec<ImportRowResultDTO>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+    Bookmarks,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct ExportQueryDTO {
+    /// Format of the export. Defaults to `json`.
+    pub format: Option<ExportFormat>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum BulkAction {
+    Delete,
+    AddTag,
+    RemoveTag,
+    Repoint,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[schema(title = "BulkFilter")]
+pub(crate) struct BulkFilterDTO {
+    /// Matches redirects whose alias or url contains the search term.
+    #[schema(examples("github"))]
+    pub q: Option<String>,
+    /// Matches redirects with this tag.
+    #[schema(examples("project-x"))]
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[schema(title = "BulkOperation")]
+pub(crate) struct BulkOperationDTO {
+    pub action: BulkAction,
+    /// The redirects to change. Either `aliases` or `filter` has to be supplied.
+    #[schema(examples(json!(["gh", "docs"])))]
+    pub aliases: Option<Vec<String>>,
+    pub filter: Option<BulkFilterDTO>,
+    /// Required for `add_tag` and `remove_tag`.
+    #[schema(examples("archived"))]
+    pub tag: Option<String>,
+    /// Required for `repoint`.
+    #[schema(examples("https://example.com"))]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum BulkItemStatus {
+    Ok,
+    NotFound,
+    Forbidden,
+    Failed,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(title = "BulkItemResult")]
+pub(crate) struct BulkItemResultDTO {
+    #[schema(examples("gh"))]
+    pub alias: String,
+    pub status: BulkItemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(title = "BulkResult")]
+pub(crate) struct BulkResultDTO {
+    pub action: BulkAction,
+    #[schema(examples(2))]
+    pub succeeded: usize,
+    #[schema(examples(0))]
+    pub failed: usize,
+    pub items: Vec<BulkItemResultDTO>,
 }
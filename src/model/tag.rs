@@ -0,0 +1,5 @@
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub(crate) struct RedirectTag {
+    pub redirect_id: String,
+    pub tag: String,
+}
@@ -43,6 +43,24 @@ pub struct DeletedUserResourceDTO {
     #[schema(examples(5))]
     pub redirects: u64,
 }
+#[derive(Serialize, Debug, sqlx::FromRow, ToSchema)]
+#[schema(title = "TrashedUser")]
+pub struct TrashedUserDTO {
+    #[schema(examples("d64bcaad-8d86-48d2-b1f3-f1c03ac30fa3"))]
+    pub id: String,
+    #[schema(examples("luke"))]
+    pub name: String,
+    /// Unix timestamp the user was moved to the trash at.
+    #[schema(examples(1772841600))]
+    pub deleted_at: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+#[schema(title = "TrashedUserList")]
+pub struct TrashedUserListDTO {
+    pub users: Vec<TrashedUserDTO>,
+}
+
 #[derive(Serialize, ToSchema)]
 #[schema(title = "UserList")]
 pub struct UserListDTO {
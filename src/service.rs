@@ -1,18 +1,31 @@
+mod analytics_service;
+mod click_service;
 mod error;
+mod export_service;
 mod login_service;
 mod redirect_service;
+mod tag_service;
 mod user_service;
 mod validator;
 use async_trait::async_trait;
+use futures_util::stream::BoxStream;
 
 use crate::model::{
-    DeletedUserDTO, FullRedirectListDTO, RedirectCreationDTO, SimpleUserDTO, UserCredentialsDTO,
-    UserDTO, UserListDTO, UserPasswordChangeDTO, UserRegistrationTokenDTO, UserTokenDTO,
+    AliasCollision, AnalyticsQueryDTO, BulkOperationDTO, BulkResultDTO, ClickEvent,
+    ClickHistogramDTO, DeletedUserDTO, ExportFormat, FullRedirectListDTO, ImportFormat,
+    ImportReportDTO, Redirect, RedirectCreationDTO, RedirectHistoryDTO, RedirectListQueryDTO,
+    RedirectStatsDTO, SimpleUserDTO, TrashedUserListDTO, UserCredentialsDTO, UserDTO, UserListDTO,
+    UserPasswordChangeDTO, UserRegistrationTokenDTO, UserTokenDTO,
 };
-pub(crate) use crate::model::{RedirectDTO, RedirectListDTO, UpdateUrlDTO};
+pub(crate) use crate::model::{RedirectDTO, RedirectListDTO, UpdateRedirectDTO};
+pub use crate::service::analytics_service::AnalyticsServiceImpl;
+pub use crate::service::click_service::ClickServiceImpl;
 pub use crate::service::error::*;
+pub use crate::service::export_service::ExportServiceImpl;
+pub(crate) use crate::service::export_service::escape_html;
 pub use crate::service::login_service::LoginServiceImpl;
 pub use crate::service::redirect_service::RedirectServiceImpl;
+pub use crate::service::tag_service::TagServiceImpl;
 pub use crate::service::user_service::UserServiceImpl;
 pub use crate::service::validator::PayloadValidator;
 pub(crate) use crate::service::validator::validate_registration_token;
@@ -20,21 +33,153 @@ use crate::{AppConfig, JwtConfig};
 
 #[async_trait]
 pub trait RedirectService {
-    async fn get_redirect(&self, alias: &str) -> Result<RedirectDTO, DbServiceError>;
-    async fn create_redirect(&self, redirect: &RedirectCreationDTO) -> Result<(), DbServiceError>;
-    async fn get_all_redirects(&self) -> Result<FullRedirectListDTO, DbServiceError>;
+    async fn get_redirect(
+        &self,
+        alias: &str,
+        path: Option<&str>,
+        query: Option<&str>,
+    ) -> Result<Redirect, DbServiceError>;
+    async fn preview_redirect(
+        &self,
+        alias: &str,
+        path: Option<&str>,
+        query: Option<&str>,
+    ) -> Result<Redirect, DbServiceError>;
+    async fn unlock_redirect(
+        &self,
+        alias: &str,
+        path: Option<&str>,
+        query: Option<&str>,
+        password: &str,
+        client: &str,
+        preview: bool,
+    ) -> Result<Redirect, DbServiceError>;
+    async fn create_redirect(
+        &self,
+        redirect: &RedirectCreationDTO,
+    ) -> Result<RedirectDTO, DbServiceError>;
+    async fn import_redirects(
+        &self,
+        owner: &str,
+        format: ImportFormat,
+        data: &[u8],
+        dry_run: bool,
+    ) -> Result<ImportReportDTO, DbServiceError>;
+    async fn get_all_redirects(
+        &self,
+        query: &RedirectListQueryDTO,
+    ) -> Result<FullRedirectListDTO, DbServiceError>;
     async fn get_all_user_redirects(
         &self,
         user_id: &str,
+        query: &RedirectListQueryDTO,
     ) -> Result<RedirectListDTO, DbServiceError>;
+    async fn get_all_trashed_redirects(
+        &self,
+        query: &RedirectListQueryDTO,
+    ) -> Result<FullRedirectListDTO, DbServiceError>;
+    async fn get_user_trashed_redirects(
+        &self,
+        user_id: &str,
+        query: &RedirectListQueryDTO,
+    ) -> Result<RedirectListDTO, DbServiceError>;
+    async fn restore_redirect_by_id(&self, id: &str) -> Result<(), DbServiceError>;
+    async fn restore_user_redirect(&self, alias: &str, user_id: &str)
+    -> Result<(), DbServiceError>;
     async fn delete_redirect_by_id(&self, id: &str) -> Result<(), DbServiceError>;
     async fn delete_user_redirect(&self, alias: &str, user_id: &str) -> Result<(), DbServiceError>;
     async fn update_redirect(
         &self,
         alias: &str,
-        redirect: &UpdateUrlDTO,
+        redirect: &UpdateRedirectDTO,
+        user_id: &str,
+    ) -> Result<RedirectDTO, DbServiceError>;
+    async fn add_secondary_alias(
+        &self,
+        alias: &str,
+        secondary_alias: &str,
         user_id: &str,
     ) -> Result<(), DbServiceError>;
+    async fn remove_secondary_alias(
+        &self,
+        alias: &str,
+        secondary_alias: &str,
+        user_id: &str,
+    ) -> Result<(), DbServiceError>;
+    async fn get_redirect_history(
+        &self,
+        alias: &str,
+        user_id: &str,
+    ) -> Result<RedirectHistoryDTO, DbServiceError>;
+    async fn rollback_redirect(
+        &self,
+        alias: &str,
+        entry_id: i64,
+        user_id: &str,
+    ) -> Result<RedirectDTO, DbServiceError>;
+    async fn set_redirect_enabled(
+        &self,
+        alias: &str,
+        enabled: bool,
+        user_id: &str,
+    ) -> Result<(), DbServiceError>;
+    async fn set_redirect_enabled_by_id(
+        &self,
+        id: &str,
+        enabled: bool,
+        admin_id: &str,
+    ) -> Result<(), DbServiceError>;
+    async fn bulk_update_redirects(
+        &self,
+        operation: &BulkOperationDTO,
+        user_id: &str,
+    ) -> Result<BulkResultDTO, DbServiceError>;
+    async fn bulk_update_user_redirects(
+        &self,
+        operation: &BulkOperationDTO,
+        user_id: &str,
+    ) -> Result<BulkResultDTO, DbServiceError>;
+    async fn get_redirect_stats(
+        &self,
+        alias: &str,
+        user_id: &str,
+    ) -> Result<RedirectStatsDTO, DbServiceError>;
+    async fn resolve_alias_collisions(&self) -> Result<Vec<AliasCollision>, DbServiceError>;
+}
+
+#[async_trait]
+pub trait ClickService {
+    fn record_click(&self, click: ClickEvent);
+    async fn shutdown(&self);
+}
+
+#[async_trait]
+pub trait AnalyticsService {
+    async fn get_redirect_histogram(
+        &self,
+        alias: &str,
+        user_id: &str,
+        query: &AnalyticsQueryDTO,
+    ) -> Result<ClickHistogramDTO, DbServiceError>;
+    async fn get_global_histogram(
+        &self,
+        query: &AnalyticsQueryDTO,
+    ) -> Result<ClickHistogramDTO, DbServiceError>;
+}
+
+pub trait ExportService {
+    fn export_redirects(
+        &self,
+        owner: Option<String>,
+        format: ExportFormat,
+    ) -> BoxStream<'static, Result<String, DbServiceError>>;
+}
+
+#[async_trait]
+pub trait TagService {
+    async fn add_tag(&self, alias: &str, tag: &str, user_id: &str) -> Result<(), DbServiceError>;
+    async fn remove_tag(&self, alias: &str, tag: &str, user_id: &str)
+    -> Result<(), DbServiceError>;
 }
 
 #[async_trait]
@@ -54,6 +199,8 @@ pub trait UserService {
     async fn get_user_info(&self, user_id: &str) -> Result<UserDTO, DbServiceError>;
     async fn get_all_users_info(&self) -> Result<UserListDTO, DbServiceError>;
     async fn delete_user(&self, user_id: &str) -> Result<DeletedUserDTO, DbServiceError>;
+    async fn get_trashed_users(&self) -> Result<TrashedUserListDTO, DbServiceError>;
+    async fn restore_user(&self, user_id: &str) -> Result<(), DbServiceError>;
     async fn create_admin_first_start(&self) -> Result<(), DbServiceError>;
     async fn change_user_pw(
         &self,
@@ -0,0 +1,240 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+
+use crate::{
+    data::{ClickRepo, RedirectRepo},
+    model::{AnalyticsQueryDTO, ClickBucketDTO, ClickHistogramDTO},
+    now_unix,
+    service::{AnalyticsService, DbServiceError},
+};
+
+pub struct AnalyticsServiceImpl {
+    redirect_repo: Arc<dyn RedirectRepo + Send + Sync>,
+    click_repo: Arc<dyn ClickRepo + Send + Sync>,
+}
+
+impl AnalyticsServiceImpl {
+    const DEFAULT_BUCKET_COUNT: i64 = 24;
+    const MAX_BUCKET_COUNT: i64 = 2000;
+
+    pub(crate) fn new(
+        redirect_repo: Arc<dyn RedirectRepo + Send + Sync>,
+        click_repo: Arc<dyn ClickRepo + Send + Sync>,
+    ) -> Self {
+        AnalyticsServiceImpl {
+            redirect_repo,
+            click_repo,
+        }
+    }
+
+    fn resolve_range(query: &AnalyticsQueryDTO) -> Result<(i64, i64), DbServiceError> {
+        let out_of_range = || {
+            DbServiceError::PayloadValidationError(
+                "range".to_owned(),
+                vec!["from and to are out of range".to_owned()],
+            )
+        };
+        let bucket_size = query.granularity.unwrap_or_default().bucket_size();
+        let to = match query.to {
+            Some(to) => to,
+            None => now_unix(),
+        };
+        let from = match query.from {
+            Some(from) => from,
+            None => to
+                .checked_sub(bucket_size * Self::DEFAULT_BUCKET_COUNT)
+                .ok_or_else(out_of_range)?,
+        };
+        if from >= to {
+            return Err(DbServiceError::PayloadValidationError(
+                "range".to_owned(),
+                vec!["from has to be before to".to_owned()],
+            ));
+        }
+
+        let from = from
+            .checked_sub(from.rem_euclid(bucket_size))
+            .ok_or_else(out_of_range)?;
+        let span = to.checked_sub(from).ok_or_else(out_of_range)?;
+        if span / bucket_size > Self::MAX_BUCKET_COUNT {
+            return Err(DbServiceError::PayloadValidationError(
+                "range".to_owned(),
+                vec![format!(
+                    "range can span at most {} buckets",
+                    Self::MAX_BUCKET_COUNT
+                )],
+            ));
+        }
+        Ok((from, to))
+    }
+
+    fn fill_buckets(
+        buckets: Vec<ClickBucketDTO>,
+        from: i64,
+        to: i64,
+        bucket_size: i64,
+    ) -> Vec<ClickBucketDTO> {
+        let counts: HashMap<i64, i64> = buckets
+            .into_iter()
+            .map(|b| (b.bucket_start, b.clicks))
+            .collect();
+        let bucket_count = to.saturating_sub(from).saturating_add(bucket_size - 1) / bucket_size;
+        (0..bucket_count)
+            .map(|i| from + i * bucket_size)
+            .map(|bucket_start| ClickBucketDTO {
+                bucket_start,
+                clicks: counts.get(&bucket_start).copied().unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    async fn histogram(
+        &self,
+        alias: Option<String>,
+        redirect_id: Option<&str>,
+        query: &AnalyticsQueryDTO,
+    ) -> Result<ClickHistogramDTO, DbServiceError> {
+        let granularity = query.granularity.unwrap_or_default();
+        let bucket_size = granularity.bucket_size();
+        let (from, to) = Self::resolve_range(query)?;
+        let buckets = self
+            .click_repo
+            .read_click_buckets(redirect_id, from, to, bucket_size)
+            .await?;
+        Ok(ClickHistogramDTO {
+            alias,
+            granularity,
+            from,
+            to,
+            buckets: Self::fill_buckets(buckets, from, to, bucket_size),
+        })
+    }
+}
+
+#[async_trait]
+impl AnalyticsService for AnalyticsServiceImpl {
+    async fn get_redirect_histogram(
+        &self,
+        alias: &str,
+        user_id: &str,
+        query: &AnalyticsQueryDTO,
+    ) -> Result<ClickHistogramDTO, DbServiceError> {
+        let redirect = self.redirect_repo.read_redirect_by_alias(alias).await?;
+        if redirect.owner != user_id {
+            return Err(DbServiceError::PermissionError(
+                "User is not authorized to view redirect analytics".to_owned(),
+            ));
+        }
+        self.histogram(Some(redirect.alias), Some(&redirect.id), query)
+            .await
+    }
+
+    async fn get_global_histogram(
+        &self,
+        query: &AnalyticsQueryDTO,
+    ) -> Result<ClickHistogramDTO, DbServiceError> {
+        self.histogram(None, None, query).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        model::{AnalyticsQueryDTO, ClickBucketDTO, Granularity},
+        service::{DbServiceError, analytics_service::AnalyticsServiceImpl},
+    };
+
+    #[test]
+    fn fill_buckets_adds_empty_buckets() {
+        let buckets = vec![ClickBucketDTO {
+            bucket_start: 7200,
+            clicks: 3,
+        }];
+        let filled = AnalyticsServiceImpl::fill_buckets(buckets, 3600, 14400, 3600);
+        let clicks: Vec<(i64, i64)> = filled.iter().map(|b| (b.bucket_start, b.clicks)).collect();
+        assert_eq!(clicks, vec![(3600, 0), (7200, 3), (10800, 0)]);
+    }
+
+    #[test]
+    fn resolve_range_aligns_from_to_bucket() {
+        let query = AnalyticsQueryDTO {
+            from: Some(3700),
+            to: Some(10000),
+            granularity: Some(Granularity::Hour),
+        };
+        let range = AnalyticsServiceImpl::resolve_range(&query);
+        assert!(range.is_ok());
+        assert_eq!(range.unwrap(), (3600, 10000));
+    }
+
+    #[test]
+    fn resolve_range_with_reversed_range_fails() {
+        let query = AnalyticsQueryDTO {
+            from: Some(10000),
+            to: Some(3600),
+            granularity: None,
+        };
+        let range = AnalyticsServiceImpl::resolve_range(&query);
+        assert!(matches!(
+            range,
+            Err(DbServiceError::PayloadValidationError(_, _))
+        ));
+    }
+
+    #[test]
+    fn resolve_range_with_too_many_buckets_fails() {
+        let query = AnalyticsQueryDTO {
+            from: Some(0),
+            to: Some(3600 * 5000),
+            granularity: Some(Granularity::Hour),
+        };
+        let range = AnalyticsServiceImpl::resolve_range(&query);
+        assert!(matches!(
+            range,
+            Err(DbServiceError::PayloadValidationError(_, _))
+        ));
+    }
+
+    #[test]
+    fn resolve_range_with_extreme_values_fails() {
+        let queries = [
+            AnalyticsQueryDTO {
+                from: Some(i64::MIN),
+                to: Some(i64::MAX),
+                granularity: None,
+            },
+            AnalyticsQueryDTO {
+                from: None,
+                to: Some(i64::MIN),
+                granularity: Some(Granularity::Day),
+            },
+            AnalyticsQueryDTO {
+                from: Some(i64::MIN),
+                to: Some(i64::MIN + 1),
+                granularity: Some(Granularity::Hour),
+            },
+        ];
+        for query in &queries {
+            assert!(matches!(
+                AnalyticsServiceImpl::resolve_range(query),
+                Err(DbServiceError::PayloadValidationError(_, _))
+            ));
+        }
+    }
+
+    #[test]
+    fn resolve_range_near_max_timestamp_succeeds() {
+        let query = AnalyticsQueryDTO {
+            from: None,
+            to: Some(i64::MAX),
+            granularity: Some(Granularity::Hour),
+        };
+        let (from, to) = AnalyticsServiceImpl::resolve_range(&query).unwrap();
+        assert_eq!(to, i64::MAX);
+        assert_eq!(
+            AnalyticsServiceImpl::fill_buckets(Vec::new(), from, to, 3600).len(),
+            25
+        );
+    }
+}
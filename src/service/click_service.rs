@@ -0,0 +1,265 @@
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use metrics::counter;
+use tokio::{
+    sync::mpsc::{self, Receiver, Sender},
+    task::JoinHandle,
+    time::Instant,
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{data::ClickRepo, model::ClickEvent, service::ClickService};
+
+pub struct ClickServiceImpl {
+    sender: Sender<ClickEvent>,
+    cancel_token: CancellationToken,
+    flush_task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Drop for ClickServiceImpl {
+    fn drop(&mut self) {
+        self.cancel_token.cancel();
+    }
+}
+
+impl ClickServiceImpl {
+    pub(crate) fn new(
+        repo: Arc<dyn ClickRepo + Send + Sync>,
+        batch_size: usize,
+        flush_interval: Duration,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel(batch_size * 10);
+        let cancel_token = CancellationToken::new();
+        let flush_task = tokio::spawn(Self::flush_task(
+            repo,
+            receiver,
+            cancel_token.clone(),
+            batch_size,
+            flush_interval,
+        ));
+        Self {
+            sender,
+            cancel_token,
+            flush_task: Mutex::new(Some(flush_task)),
+        }
+    }
+
+    async fn flush_task(
+        repo: Arc<dyn ClickRepo + Send + Sync>,
+        mut receiver: Receiver<ClickEvent>,
+        cancel_token: CancellationToken,
+        batch_size: usize,
+        interval: Duration,
+    ) {
+        let start = Instant::now() + interval;
+        let mut timer = tokio::time::interval_at(start, interval);
+        timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut batch = Vec::with_capacity(batch_size);
+        loop {
+            tokio::select! {
+                event = receiver.recv() => {
+                    let Some(event) = event else {
+                        Self::flush(repo.as_ref(), &mut batch).await;
+                        break;
+                    };
+                    batch.push(event);
+                    if batch.len() >= batch_size {
+                        Self::flush(repo.as_ref(), &mut batch).await;
+                    }
+                }
+                _ = timer.tick() => {
+                    Self::flush(repo.as_ref(), &mut batch).await;
+                }
+                () = cancel_token.cancelled() => {
+                    // persist the events still waiting in the channel before stopping
+                    receiver.close();
+                    while let Ok(event) = receiver.try_recv() {
+                        batch.push(event);
+                        if batch.len() >= batch_size {
+                            Self::flush(repo.as_ref(), &mut batch).await;
+                        }
+                    }
+                    Self::flush(repo.as_ref(), &mut batch).await;
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn flush(repo: &(dyn ClickRepo + Send + Sync), batch: &mut Vec<ClickEvent>) {
+        if batch.is_empty() {
+            return;
+        }
+        if let Err(e) = repo.create_click_events(batch).await {
+            eprintln!("Failed to persist {} click events: {e}", batch.len());
+            counter!("via_alias_click_events_dropped_total").increment(batch.len() as u64);
+        }
+        batch.clear();
+    }
+}
+
+#[async_trait]
+impl ClickService for ClickServiceImpl {
+    fn record_click(&self, click: ClickEvent) {
+        if self.sender.try_send(click).is_err() {
+            counter!("via_alias_click_events_dropped_total").increment(1);
+        }
+    }
+
+    async fn shutdown(&self) {
+        self.cancel_token.cancel();
+        let flush_task = self
+            .flush_task
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(flush_task) = flush_task
+            && let Err(e) = flush_task.await
+        {
+            eprintln!("Failed to flush click events: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+
+    use crate::{
+        data::ClickRepo,
+        model::{ClickBucketDTO, ClickEvent},
+        service::{ClickService, click_service::ClickServiceImpl},
+    };
+
+    #[derive(Default)]
+    struct RecordingClickRepo {
+        batches: Mutex<Vec<usize>>,
+        fail: bool,
+    }
+
+    impl RecordingClickRepo {
+        fn batches(&self) -> Vec<usize> {
+            self.batches.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl ClickRepo for RecordingClickRepo {
+        async fn create_click_events(&self, events: &[ClickEvent]) -> Result<u64, sqlx::Error> {
+            if self.fail {
+                return Err(sqlx::Error::PoolClosed);
+            }
+            self.batches.lock().unwrap().push(events.len());
+            Ok(events.len() as u64)
+        }
+
+        async fn read_last_click_by_redirect_id(
+            &self,
+            _redirect_id: &str,
+        ) -> Result<Option<i64>, sqlx::Error> {
+            Ok(None)
+        }
+
+        async fn read_click_buckets(
+            &self,
+            _redirect_id: Option<&str>,
+            _from: i64,
+            _to: i64,
+            _bucket_size: i64,
+        ) -> Result<Vec<ClickBucketDTO>, sqlx::Error> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn click() -> ClickEvent {
+        ClickEvent::new("1".to_owned(), None, None)
+    }
+
+    #[tokio::test]
+    async fn flushes_full_batches_and_remaining_clicks_on_shutdown() {
+        let repo = Arc::new(RecordingClickRepo::default());
+        let service = ClickServiceImpl::new(repo.clone(), 3, Duration::from_secs(3600));
+        for _ in 0..7 {
+            service.record_click(click());
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(repo.batches(), vec![3, 3]);
+
+        service.shutdown().await;
+        assert_eq!(repo.batches(), vec![3, 3, 1]);
+    }
+
+    #[tokio::test]
+    async fn flushes_partial_batch_after_interval() {
+        let repo = Arc::new(RecordingClickRepo::default());
+        let service = ClickServiceImpl::new(repo.clone(), 500, Duration::from_millis(50));
+        service.record_click(click());
+        service.record_click(click());
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(repo.batches(), vec![2]);
+    }
+
+    #[tokio::test]
+    async fn shutdown_flushes_clicks_not_yet_received() {
+        let repo = Arc::new(RecordingClickRepo::default());
+        let service = ClickServiceImpl::new(repo.clone(), 2, Duration::from_secs(3600));
+        // the flush task hasn't run yet, all clicks are still in the channel
+        for _ in 0..5 {
+            service.record_click(click());
+        }
+        service.shutdown().await;
+        assert_eq!(repo.batches(), vec![2, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn counts_clicks_dropped_by_full_channel() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        let repo = Arc::new(RecordingClickRepo::default());
+        let service = ClickServiceImpl::new(repo.clone(), 1, Duration::from_secs(3600));
+        // the channel holds ten times the batch size
+        metrics::with_local_recorder(&recorder, || {
+            for _ in 0..12 {
+                service.record_click(click());
+            }
+        });
+        assert!(
+            handle
+                .render()
+                .contains("via_alias_click_events_dropped_total 2")
+        );
+        service.shutdown().await;
+        assert_eq!(repo.batches().len(), 10);
+    }
+
+    #[tokio::test]
+    async fn counts_clicks_dropped_by_failed_flush() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        let repo = RecordingClickRepo {
+            fail: true,
+            ..Default::default()
+        };
+        let mut batch = vec![click(), click(), click()];
+        {
+            let _guard = metrics::set_default_local_recorder(&recorder);
+            ClickServiceImpl::flush(&repo, &mut batch).await;
+        }
+        assert!(batch.is_empty());
+        assert!(
+            handle
+                .render()
+                .contains("via_alias_click_events_dropped_total 3")
+        );
+    }
+}
@@ -6,10 +6,13 @@ use axum::{
     response::{IntoResponse, Response},
 };
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum DbServiceError {
     NotFoundError,
+    Gone(String),
+    Inactive(Option<String>),
     DatabaseError(String),
     PayloadValidationError(String, Vec<String>),
     AuthError(String),
@@ -17,9 +20,11 @@ pub enum DbServiceError {
     TokenInvalid,
     ResourceConflict,
     InvalidCredentials,
+    TooManyRequests,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(title = "ValidationError")]
 pub(crate) struct ValidationErrorResponse {
     pub(crate) on_item: String,
     pub(crate) errors: Vec<String>,
@@ -29,6 +34,8 @@ impl fmt::Display for DbServiceError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             DbServiceError::NotFoundError => write!(f, "Resource not found"),
+            DbServiceError::Gone(msg) => write!(f, "Resource is no longer available: {msg}"),
+            DbServiceError::Inactive(_) => write!(f, "Resource is not active"),
             DbServiceError::DatabaseError(msg) => write!(f, "Database error: {msg}"),
             DbServiceError::PayloadValidationError(s, items) => {
                 let formatted_vec = items
@@ -43,13 +50,17 @@ impl fmt::Display for DbServiceError {
             DbServiceError::PermissionError(msg) => write!(f, "Permission error: {msg}"),
             DbServiceError::ResourceConflict => write!(f, "Resource already exists"),
             DbServiceError::InvalidCredentials => write!(f, "Wrong username or password"),
+            DbServiceError::TooManyRequests => write!(f, "Too many requests"),
         }
     }
 }
 impl IntoResponse for DbServiceError {
     fn into_response(self) -> Response {
         match self {
-            DbServiceError::NotFoundError => StatusCode::NOT_FOUND.into_response(),
+            DbServiceError::NotFoundError | DbServiceError::Inactive(_) => {
+                StatusCode::NOT_FOUND.into_response()
+            }
+            DbServiceError::Gone(msg) => (StatusCode::GONE, msg).into_response(),
             DbServiceError::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
             DbServiceError::PayloadValidationError(s, e) => {
                 let errors = ValidationErrorResponse {
@@ -64,6 +75,7 @@ impl IntoResponse for DbServiceError {
             }
             DbServiceError::ResourceConflict => StatusCode::CONFLICT.into_response(),
             DbServiceError::InvalidCredentials => StatusCode::BAD_REQUEST.into_response(),
+            DbServiceError::TooManyRequests => StatusCode::TOO_MANY_REQUESTS.into_response(),
         }
     }
 }
@@ -74,7 +86,8 @@ impl From<sqlx::Error> for DbServiceError {
         match err {
             sqlx::Error::RowNotFound => DbServiceError::NotFoundError,
             sqlx::Error::Database(e) => {
-                if e.is_unique_violation() {
+                // SQLITE_CONSTRAINT_TRIGGER, raised when an alias is taken by the other alias table
+                if e.is_unique_violation() || e.code().as_deref() == Some("1811") {
                     DbServiceError::ResourceConflict
                 } else {
                     DbServiceError::DatabaseError(e.to_string())
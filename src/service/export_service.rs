@@ -0,0 +1,425 @@
+use std::{collections::BTreeMap, future::ready, sync::Arc};
+
+use futures_util::{
+    StreamExt,
+    stream::{self, BoxStream},
+};
+use serde::Serialize;
+
+use crate::{
+    data::{RedirectRepo, TagRepo},
+    model::{ExportFormat, Redirect, RedirectCursor, RedirectPageQuery, RedirectType},
+    now_unix,
+    service::{
+        DbServiceError, ExportService, redirect_service::attach_aliases, tag_service::attach_tags,
+    },
+};
+
+pub(crate) fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// only the fields the import reads, the password hash is never exported, only whether the
+// redirect has one
+#[derive(Serialize)]
+struct JsonRow<'a> {
+    alias: &'a str,
+    url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_clicks: Option<i64>,
+    redirect_type: RedirectType,
+    passthrough: bool,
+    template: bool,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    query_params: &'a BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    case_insensitive: Option<bool>,
+    preview: bool,
+    protected: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    active_from: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    active_until: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fallback_url: Option<&'a str>,
+    enabled: bool,
+}
+
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    alias: &'a str,
+    url: &'a str,
+    expires_at: Option<i64>,
+    max_clicks: Option<i64>,
+    redirect_type: RedirectType,
+    passthrough: bool,
+    template: bool,
+    query_params: String,
+    case_insensitive: Option<bool>,
+    preview: bool,
+    protected: bool,
+    active_from: Option<i64>,
+    active_until: Option<i64>,
+    fallback_url: Option<&'a str>,
+    enabled: bool,
+    tags: String,
+    aliases: String,
+    owner: &'a str,
+    clicks: i64,
+    created_at: i64,
+    updated_at: i64,
+}
+
+pub struct ExportServiceImpl {
+    redirect_repo: Arc<dyn RedirectRepo + Send + Sync>,
+    tag_repo: Arc<dyn TagRepo + Send + Sync>,
+}
+
+impl ExportServiceImpl {
+    const PAGE_SIZE: i64 = 500;
+    const CSV_HEADER: &str = "alias,url,expires_at,max_clicks,redirect_type,passthrough,template,query_params,case_insensitive,preview,protected,active_from,active_until,fallback_url,enabled,tags,aliases,owner,clicks,created_at,updated_at\n";
+    const BOOKMARKS_HEADER: &str = "<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">
+<TITLE>Via-Alias</TITLE>
+<H1>Via-Alias</H1>
+<DL><p>
+";
+
+    pub(crate) fn new(
+        redirect_repo: Arc<dyn RedirectRepo + Send + Sync>,
+        tag_repo: Arc<dyn TagRepo + Send + Sync>,
+    ) -> Self {
+        ExportServiceImpl {
+            redirect_repo,
+            tag_repo,
+        }
+    }
+
+    fn header(format: ExportFormat) -> &'static str {
+        match format {
+            ExportFormat::Json => "[",
+            ExportFormat::Csv => Self::CSV_HEADER,
+            ExportFormat::Bookmarks => Self::BOOKMARKS_HEADER,
+        }
+    }
+
+    fn footer(format: ExportFormat) -> &'static str {
+        match format {
+            ExportFormat::Json => "]",
+            ExportFormat::Csv => "",
+            ExportFormat::Bookmarks => "</DL><p>\n",
+        }
+    }
+
+    fn render(
+        format: ExportFormat,
+        redirect: &Redirect,
+        first: bool,
+    ) -> Result<String, DbServiceError> {
+        let export_error = |e: &dyn std::error::Error| {
+            DbServiceError::DatabaseError(format!("failed to export redirect: {e}"))
+        };
+        match format {
+            ExportFormat::Json => {
+                let json = serde_json::to_string(&JsonRow {
+                    alias: &redirect.alias,
+                    url: &redirect.url,
+                    expires_at: redirect.expires_at,
+                    max_clicks: redirect.max_clicks,
+                    redirect_type: redirect.redirect_type,
+                    passthrough: redirect.passthrough,
+                    template: redirect.template,
+                    query_params: &redirect.query_params,
+                    case_insensitive: redirect.case_insensitive,
+                    preview: redirect.preview,
+                    protected: redirect.pwhash.is_some(),
+                    active_from: redirect.active_from,
+                    active_until: redirect.active_until,
+                    fallback_url: redirect.fallback_url.as_deref(),
+                    enabled: redirect.enabled,
+                })
+                .map_err(|e| export_error(&e))?;
+                Ok(if first { json } else { format!(",{json}") })
+            }
+            ExportFormat::Csv => {
+                let mut writer = csv::WriterBuilder::new()
+                    .has_headers(false)
+                    .from_writer(Vec::new());
+                writer
+                    .serialize(CsvRow {
+                        alias: &redirect.alias,
+                        url: &redirect.url,
+                        expires_at: redirect.expires_at,
+                        max_clicks: redirect.max_clicks,
+                        redirect_type: redirect.redirect_type,
+                        passthrough: redirect.passthrough,
+                        template: redirect.template,
+                        query_params: if redirect.query_params.is_empty() {
+                            String::new()
+                        } else {
+                            serde_json::to_string(&redirect.query_params)
+                                .map_err(|e| export_error(&e))?
+                        },
+                        case_insensitive: redirect.case_insensitive,
+                        preview: redirect.preview,
+                        protected: redirect.pwhash.is_some(),
+                        active_from: redirect.active_from,
+                        active_until: redirect.active_until,
+                        fallback_url: redirect.fallback_url.as_deref(),
+                        enabled: redirect.enabled,
+                        tags: redirect.tags.join(" "),
+                        aliases: redirect.aliases.join(" "),
+                        owner: &redirect.owner,
+                        clicks: redirect.clicks,
+                        created_at: redirect.created_at,
+                        updated_at: redirect.updated_at,
+                    })
+                    .map_err(|e| export_error(&e))?;
+                let row = writer.into_inner().map_err(|e| export_error(&e))?;
+                String::from_utf8(row).map_err(|e| export_error(&e))
+            }
+            ExportFormat::Bookmarks => Ok(format!(
+                "    <DT><A HREF=\"{}\" ADD_DATE=\"{}\" LAST_MODIFIED=\"{}\" TAGS=\"{}\">{}</A>\n",
+                escape_html(&redirect.url),
+                redirect.created_at,
+                redirect.updated_at,
+                escape_html(&redirect.tags.join(",")),
+                escape_html(&redirect.alias),
+            )),
+        }
+    }
+
+    async fn read_page(
+        redirect_repo: &dyn RedirectRepo,
+        tag_repo: &dyn TagRepo,
+        owner: Option<&str>,
+        after: Option<RedirectCursor>,
+    ) -> Result<(Vec<Redirect>, Option<RedirectCursor>), DbServiceError> {
+        let page_query = RedirectPageQuery {
+            limit: Self::PAGE_SIZE,
+            after,
+            ..Default::default()
+        };
+        let mut page = match owner {
+            Some(owner) => {
+                redirect_repo
+                    .read_all_redirects_by_user_id(owner, &page_query)
+                    .await?
+            }
+            None => redirect_repo.read_all_redirects(&page_query).await?,
+        };
+        attach_tags(tag_repo, &mut page.redirects).await?;
+        attach_aliases(redirect_repo, &mut page.redirects).await?;
+        let now = now_unix();
+        for redirect in &mut page.redirects {
+            redirect.state = redirect.state_at(now);
+        }
+        Ok((page.redirects, page.next_cursor))
+    }
+}
+
+impl ExportService for ExportServiceImpl {
+    fn export_redirects(
+        &self,
+        owner: Option<String>,
+        format: ExportFormat,
+    ) -> BoxStream<'static, Result<String, DbServiceError>> {
+        let redirect_repo = self.redirect_repo.clone();
+        let tag_repo = self.tag_repo.clone();
+        // Some(cursor) reads the next page, None ends the export
+        let pages = stream::unfold(Some(None), move |after| {
+            let redirect_repo = redirect_repo.clone();
+            let tag_repo = tag_repo.clone();
+            let owner = owner.clone();
+            async move {
+                let page = Self::read_page(
+                    redirect_repo.as_ref(),
+                    tag_repo.as_ref(),
+                    owner.as_deref(),
+                    after?,
+                )
+                .await;
+                match page {
+                    Ok((redirects, next_cursor)) => Some((Ok(redirects), next_cursor.map(Some))),
+                    Err(e) => Some((Err(e), None)),
+                }
+            }
+        });
+
+        let mut first = true;
+        let rows = pages.map(move |page| {
+            let mut chunk = String::new();
+            for redirect in &page? {
+                chunk.push_str(&Self::render(format, redirect, first)?);
+                first = false;
+            }
+            Ok(chunk)
+        });
+
+        stream::once(ready(Ok(Self::header(format).to_owned())))
+            .chain(rows)
+            .chain(stream::once(ready(Ok(Self::footer(format).to_owned()))))
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures_util::StreamExt;
+    use sqlx::SqlitePool;
+
+    use crate::{
+        data::{RedirectRepoSqliteImpl, TagRepo, TagRepoSqliteImpl},
+        model::{ExportFormat, Redirect, RedirectDTO, RedirectType},
+        service::{ExportService, export_service::ExportServiceImpl},
+    };
+
+    fn test_redirect() -> Redirect {
+        Redirect {
+            alias: "gh".to_owned(),
+            url: "https://github.com/?a=1&b=\"2\"".to_owned(),
+            owner: "some_id_string".to_owned(),
+            redirect_type: RedirectType::PermanentRedirect,
+            tags: vec!["code".to_owned(), "git".to_owned()],
+            aliases: vec!["github".to_owned()],
+            query_params: [("utm_source".to_owned(), "news".to_owned())].into(),
+            created_at: 100,
+            updated_at: 200,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn render_csv_quotes_fields() {
+        let row = ExportServiceImpl::render(ExportFormat::Csv, &test_redirect(), true).unwrap();
+        assert_eq!(
+            row,
+            "gh,\"https://github.com/?a=1&b=\"\"2\"\"\",,,308,false,false,\"{\"\"utm_source\"\":\"\"news\"\"}\",,false,false,,,,true,code git,github,some_id_string,0,100,200\n"
+        );
+    }
+
+    #[test]
+    fn render_json_separates_rows() {
+        let first = ExportServiceImpl::render(ExportFormat::Json, &test_redirect(), true).unwrap();
+        let second =
+            ExportServiceImpl::render(ExportFormat::Json, &test_redirect(), false).unwrap();
+        assert!(first.starts_with('{'));
+        assert_eq!(second, format!(",{first}"));
+    }
+
+    #[test]
+    fn render_json_round_trips_through_import() {
+        let redirect = Redirect {
+            enabled: false,
+            fallback_url: Some("https://example.com".to_owned()),
+            ..test_redirect()
+        };
+        let json = ExportServiceImpl::render(ExportFormat::Json, &redirect, true).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        for internal in [
+            "id",
+            "owner",
+            "clicks",
+            "locked",
+            "created_at",
+            "updated_by",
+        ] {
+            assert!(value.get(internal).is_none(), "{internal} is exported");
+        }
+        let imported: RedirectDTO = serde_json::from_str(&json).unwrap();
+        assert_eq!(imported.alias, redirect.alias);
+        assert_eq!(imported.url, redirect.url);
+        assert_eq!(imported.redirect_type, redirect.redirect_type);
+        assert_eq!(imported.query_params, redirect.query_params);
+        assert_eq!(imported.fallback_url, redirect.fallback_url);
+        assert!(!imported.enabled);
+    }
+
+    #[test]
+    fn render_marks_protected_redirects() {
+        let redirect = Redirect {
+            pwhash: Some("not_a_pw_hash".to_owned()),
+            ..test_redirect()
+        };
+        let json = ExportServiceImpl::render(ExportFormat::Json, &redirect, true).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["protected"], true);
+        assert!(json.get("pwhash").is_none());
+
+        let csv = ExportServiceImpl::render(ExportFormat::Csv, &redirect, true).unwrap();
+        assert!(csv.contains(",false,true,,,,true,"));
+    }
+
+    #[test]
+    fn render_bookmarks_escapes_html() {
+        let row =
+            ExportServiceImpl::render(ExportFormat::Bookmarks, &test_redirect(), true).unwrap();
+        assert_eq!(
+            row,
+            "    <DT><A HREF=\"https://github.com/?a=1&amp;b=&quot;2&quot;\" ADD_DATE=\"100\" LAST_MODIFIED=\"200\" TAGS=\"code,git\">gh</A>\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn export_streams_all_pages() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        sqlx::query("INSERT INTO users (id, name, pwhash, is_admin) VALUES ('owner', 'testuser', 'not_a_pw_hash', false);")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 501)
+            INSERT INTO redirects (id, alias, url, owner)
+            SELECT 'id' || i, 'alias' || i, 'https://someurl.com/' || i, 'owner' FROM n;",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let tag_repo = Arc::new(TagRepoSqliteImpl::new(pool.clone()));
+        tag_repo.add_tag_to_redirect("id1", "first").await.unwrap();
+        let service = ExportServiceImpl::new(
+            Arc::new(RedirectRepoSqliteImpl::new(pool.clone())),
+            tag_repo,
+        );
+
+        let chunks: Vec<_> = service
+            .export_redirects(Some("owner".to_owned()), ExportFormat::Json)
+            .collect()
+            .await;
+        let body: String = chunks.into_iter().map(|c| c.unwrap()).collect();
+        let exported: Vec<RedirectDTO> = serde_json::from_str(&body).unwrap();
+        assert_eq!(exported.len(), 501);
+        assert!(!body.contains("\"owner\""));
+
+        let chunks: Vec<_> = service
+            .export_redirects(Some("owner".to_owned()), ExportFormat::Csv)
+            .collect()
+            .await;
+        let body: String = chunks.into_iter().map(|c| c.unwrap()).collect();
+        assert_eq!(body.lines().count(), 502);
+        assert!(body.contains(",first,"));
+
+        let other_owner: Vec<_> = service
+            .export_redirects(Some("other".to_owned()), ExportFormat::Json)
+            .collect()
+            .await;
+        let body: String = other_owner.into_iter().map(|c| c.unwrap()).collect();
+        assert_eq!(body, "[]");
+    }
+}
@@ -1,24 +1,275 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex, PoisonError},
+};
 
+use argon2::password_hash::rand_core::{OsRng, RngCore};
 use async_trait::async_trait;
+use base64::{Engine, engine::general_purpose};
+use futures_util::{StreamExt, stream};
 use uuid::Uuid;
 
 use crate::{
-    data::RedirectRepo,
+    AliasConfig,
+    data::{ClickRepo, RedirectRepo, RedirectUpdateError, TagRepo},
     model::{
-        FullRedirectListDTO, Redirect, RedirectCreationDTO, RedirectDTO, RedirectListDTO,
-        UpdateUrlDTO,
+        AliasCollision, BulkAction, BulkFilterDTO, BulkItemResultDTO, BulkItemStatus,
+        BulkOperationDTO, BulkResultDTO, CursorValue, FullRedirectListDTO, ImportFormat,
+        ImportReportDTO, ImportRowResultDTO, ImportRowStatus, Redirect, RedirectCreationDTO,
+        RedirectCursor, RedirectDTO, RedirectHistoryDTO, RedirectListDTO, RedirectListQueryDTO,
+        RedirectPageQuery, RedirectSortKey, RedirectState, RedirectStatsDTO, SortOrder,
+        UpdateRedirectDTO,
+    },
+    now_unix,
+    service::{
+        PayloadValidator, RedirectService, TagServiceImpl, UserServiceImpl,
+        ValidationErrorResponse, error::DbServiceError, tag_service::attach_tags,
+        validator::verify_password_hash,
     },
-    service::{PayloadValidator, RedirectService, error::DbServiceError},
 };
 
+pub(crate) async fn attach_aliases(
+    repo: &dyn RedirectRepo,
+    redirects: &mut [Redirect],
+) -> Result<(), DbServiceError> {
+    let ids: Vec<String> = redirects.iter().map(|r| r.id.clone()).collect();
+    let mut aliases: HashMap<String, Vec<String>> = HashMap::new();
+    for redirect_alias in repo.read_secondary_aliases_by_redirect_ids(&ids).await? {
+        aliases
+            .entry(redirect_alias.redirect_id)
+            .or_default()
+            .push(redirect_alias.alias);
+    }
+    for redirect in redirects {
+        redirect.aliases = aliases.remove(&redirect.id).unwrap_or_default();
+    }
+    Ok(())
+}
+
+enum BulkChange<'a> {
+    Delete,
+    AddTag(&'a str),
+    RemoveTag(&'a str),
+    Repoint(&'a str),
+}
+
 pub struct RedirectServiceImpl {
     repo: Arc<dyn RedirectRepo + Send + Sync>,
+    click_repo: Arc<dyn ClickRepo + Send + Sync>,
+    tag_repo: Arc<dyn TagRepo + Send + Sync>,
+    alias_config: AliasConfig,
+    // failed password attempts per redirect id and client, with the start of the window
+    password_failures: Mutex<HashMap<(String, String), (i64, u32)>>,
 }
 impl RedirectServiceImpl {
-    pub(crate) fn new(repo: Arc<dyn RedirectRepo + Send + Sync>) -> Self {
-        RedirectServiceImpl { repo }
+    const MAX_ALIAS_ATTEMPTS: usize = 10;
+    const MAX_IMPORT_ROWS: usize = 10_000;
+    const IMPORT_HASH_CONCURRENCY: usize = 4;
+    const MAX_BULK_ITEMS: usize = 1000;
+    const MAX_QUERY_PARAMS: usize = 20;
+    const MAX_SECONDARY_ALIASES: usize = 20;
+    const MAX_PASSWORD_FAILURES: u32 = 5;
+    const PASSWORD_FAILURE_WINDOW: i64 = 300;
+
+    pub(crate) fn new(
+        repo: Arc<dyn RedirectRepo + Send + Sync>,
+        click_repo: Arc<dyn ClickRepo + Send + Sync>,
+        tag_repo: Arc<dyn TagRepo + Send + Sync>,
+        alias_config: AliasConfig,
+    ) -> Self {
+        RedirectServiceImpl {
+            repo,
+            click_repo,
+            tag_repo,
+            alias_config,
+            password_failures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn with_details(
+        &self,
+        mut redirects: Vec<Redirect>,
+    ) -> Result<Vec<Redirect>, DbServiceError> {
+        attach_tags(self.tag_repo.as_ref(), &mut redirects).await?;
+        attach_aliases(self.repo.as_ref(), &mut redirects).await?;
+        let now = now_unix();
+        for redirect in &mut redirects {
+            redirect.state = redirect.state_at(now);
+        }
+        Ok(redirects)
+    }
+
+    async fn resolve_alias(&self, alias: &str) -> Result<Redirect, DbServiceError> {
+        match self.repo.read_redirect_by_alias(alias).await {
+            Err(sqlx::Error::RowNotFound) => {}
+            res => return Ok(res?),
+        }
+        match self.repo.read_redirect_by_secondary_alias(alias).await {
+            Err(sqlx::Error::RowNotFound) => {}
+            res => return Ok(res?),
+        }
+        let redirect = self.repo.read_redirect_by_alias_key(alias).await?;
+        if !redirect
+            .case_insensitive
+            .unwrap_or(self.alias_config.case_insensitive)
+        {
+            return Err(DbServiceError::NotFoundError);
+        }
+        Ok(redirect)
+    }
+
+    async fn resolve_target(
+        &self,
+        alias: &str,
+        path: Option<&str>,
+        query: Option<&str>,
+        consume_click: bool,
+        password: Option<(&str, &str)>,
+    ) -> Result<Redirect, DbServiceError> {
+        let mut redirect = self.resolve_alias(alias).await?;
+        match redirect.state_at(now_unix()) {
+            RedirectState::Disabled | RedirectState::Scheduled | RedirectState::Ended => {
+                return Err(DbServiceError::Inactive(redirect.fallback_url));
+            }
+            RedirectState::Expired => {
+                return Err(Self::gone(redirect.fallback_url, "Redirect has expired"));
+            }
+            RedirectState::Deleted => return Err(DbServiceError::NotFoundError),
+            RedirectState::Active | RedirectState::Exhausted => {}
+        }
+        if let Some(pwhash) = &redirect.pwhash {
+            let (password, client) = password.ok_or_else(|| {
+                DbServiceError::AuthError("Redirect requires a password".to_owned())
+            })?;
+            self.verify_redirect_password(&redirect.id, client, password, pwhash)
+                .await?;
+        }
+        if path.is_some() && !redirect.passthrough && !redirect.template {
+            return Err(DbServiceError::NotFoundError);
+        }
+        if redirect.template {
+            redirect.url = Self::fill_template(&redirect.url, path, query)
+                .ok_or(DbServiceError::NotFoundError)?;
+        }
+        // a preview must not use up one of the limited clicks
+        let click_available = if consume_click {
+            redirect.max_clicks.is_none()
+                || self.repo.consume_redirect_click(&redirect.id).await? > 0
+        } else {
+            redirect.remaining_clicks != Some(0)
+        };
+        if !click_available {
+            return Err(Self::gone(
+                redirect.fallback_url,
+                "Redirect has reached its click limit",
+            ));
+        }
+        if redirect.passthrough {
+            redirect.url = Self::passthrough_url(&redirect.url, path, query);
+        }
+        if !redirect.query_params.is_empty() {
+            redirect.url = Self::append_query_params(&redirect.url, &redirect.query_params);
+        }
+        Ok(redirect)
+    }
+
+    // redirects that can no longer be followed send visitors to their fallback url if they have one
+    fn gone(fallback_url: Option<String>, reason: &str) -> DbServiceError {
+        match fallback_url {
+            Some(url) => DbServiceError::Inactive(Some(url)),
+            None => DbServiceError::Gone(reason.to_owned()),
+        }
+    }
+
+    async fn verify_redirect_password(
+        &self,
+        redirect_id: &str,
+        client: &str,
+        password: &str,
+        pwhash: &str,
+    ) -> Result<(), DbServiceError> {
+        let key = (redirect_id.to_owned(), client.to_owned());
+        let now = now_unix();
+        // every attempt counts until it succeeds, so parallel guesses can't exceed the limit
+        {
+            let mut failures = self.lock_password_failures();
+            failures.retain(|_, &mut (since, _)| since > now - Self::PASSWORD_FAILURE_WINDOW);
+            let entry = failures.entry(key.clone()).or_insert((now, 0));
+            if entry.1 >= Self::MAX_PASSWORD_FAILURES {
+                return Err(DbServiceError::TooManyRequests);
+            }
+            entry.1 += 1;
+        }
+
+        let (password, pwhash) = (password.to_owned(), pwhash.to_owned());
+        let result = tokio::task::spawn_blocking(move || verify_password_hash(&password, &pwhash))
+            .await
+            .map_err(|e| DbServiceError::DatabaseError(e.to_string()))?;
+        if result.is_ok() {
+            self.lock_password_failures().remove(&key);
+        }
+        result
+    }
+
+    fn lock_password_failures(
+        &self,
+    ) -> std::sync::MutexGuard<'_, HashMap<(String, String), (i64, u32)>> {
+        self.password_failures
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn validate_password(password: Option<&str>) -> Result<(), DbServiceError> {
+        match password {
+            Some(password) => PayloadValidator::new(password)
+                .min_length(8)
+                .max_length(100)
+                .validate()
+                .map_err(|e| DbServiceError::PayloadValidationError("password".to_string(), e)),
+            None => Ok(()),
+        }
+    }
+
+    async fn hash_password(password: Option<&str>) -> Result<Option<String>, DbServiceError> {
+        let Some(password) = password.map(str::to_owned) else {
+            return Ok(None);
+        };
+        tokio::task::spawn_blocking(move || UserServiceImpl::create_password_hash_string(&password))
+            .await
+            .map_err(|e| DbServiceError::DatabaseError(e.to_string()))?
+            .map(Some)
+            .map_err(|e| DbServiceError::DatabaseError(e.to_string()))
+    }
+
+    async fn read_owned_redirect(
+        &self,
+        alias: &str,
+        user_id: &str,
+    ) -> Result<Redirect, DbServiceError> {
+        let redirect = self.repo.read_redirect_by_alias(alias).await?;
+        if redirect.owner != user_id {
+            return Err(DbServiceError::PermissionError(
+                "User is not authorized to change redirect".to_owned(),
+            ));
+        }
+        Ok(redirect)
+    }
+
+    fn generate_alias(config: &AliasConfig) -> String {
+        let alphabet = &config.alphabet;
+        let len = u32::try_from(alphabet.len()).unwrap_or(u32::MAX);
+        // reject values above the largest multiple of len to avoid modulo bias
+        let zone = u32::MAX - u32::MAX % len;
+        let mut alias = String::with_capacity(config.length);
+        while alias.len() < config.length {
+            let value = OsRng.next_u32();
+            if value < zone {
+                alias.push(alphabet[(value % len) as usize]);
+            }
+        }
+        alias
     }
+
     fn validate_alias(alias: &str) -> Result<(), DbServiceError> {
         PayloadValidator::new(alias)
             .not_empty()
@@ -32,6 +283,580 @@ impl RedirectServiceImpl {
             .map_err(|e| DbServiceError::PayloadValidationError("alias".to_string(), e))
     }
 
+    fn validate_expires_at(expires_at: Option<i64>) -> Result<(), DbServiceError> {
+        match expires_at {
+            Some(exp) if exp <= now_unix() => Err(DbServiceError::PayloadValidationError(
+                "expires_at".to_string(),
+                vec!["has to be in the future".to_owned()],
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn validate_activation_window(
+        active_from: Option<i64>,
+        active_until: Option<i64>,
+    ) -> Result<(), DbServiceError> {
+        match (active_from, active_until) {
+            (Some(from), Some(until)) if from >= until => {
+                Err(DbServiceError::PayloadValidationError(
+                    "active_until".to_string(),
+                    vec!["has to be after active_from".to_owned()],
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn validate_fallback_url(fallback_url: Option<&str>) -> Result<(), DbServiceError> {
+        match fallback_url {
+            Some(url) => PayloadValidator::new(url)
+                .not_empty()
+                .max_length(2048)
+                .has_url_schema()
+                .validate()
+                .map_err(|e| DbServiceError::PayloadValidationError("fallback_url".to_string(), e)),
+            None => Ok(()),
+        }
+    }
+
+    fn validate_max_clicks(max_clicks: Option<i64>) -> Result<(), DbServiceError> {
+        match max_clicks {
+            Some(max) if max < 1 => Err(DbServiceError::PayloadValidationError(
+                "max_clicks".to_string(),
+                vec!["has to be at least 1".to_owned()],
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    // exports only contain whether a redirect is protected, importing it unprotected would
+    // make it public
+    fn validate_import_protection(
+        protected: bool,
+        password: Option<&str>,
+    ) -> Result<(), DbServiceError> {
+        match (protected, password) {
+            (true, None) => Err(DbServiceError::PayloadValidationError(
+                "password".to_string(),
+                vec!["is required for protected redirects".to_owned()],
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn parse_import(
+        format: ImportFormat,
+        data: &[u8],
+    ) -> Result<Vec<Result<RedirectDTO, String>>, DbServiceError> {
+        match format {
+            ImportFormat::Csv => {
+                let mut reader = csv::ReaderBuilder::new()
+                    .trim(csv::Trim::All)
+                    .from_reader(data);
+                Ok(reader
+                    .deserialize::<RedirectDTO>()
+                    .map(|row| row.map_err(|e| e.to_string()))
+                    .collect())
+            }
+            ImportFormat::Json => {
+                let rows: Vec<serde_json::Value> = serde_json::from_slice(data).map_err(|e| {
+                    DbServiceError::PayloadValidationError("body".to_string(), vec![e.to_string()])
+                })?;
+                Ok(rows
+                    .into_iter()
+                    .map(|row| serde_json::from_value(row).map_err(|e| e.to_string()))
+                    .collect())
+            }
+        }
+    }
+
+    fn validation_errors(redirect: &RedirectDTO) -> Vec<ValidationErrorResponse> {
+        [
+            RedirectServiceImpl::validate_alias(&redirect.alias),
+            RedirectServiceImpl::validate_url(&redirect.url),
+            RedirectServiceImpl::validate_template(
+                &redirect.url,
+                redirect.template,
+                redirect.passthrough,
+            ),
+            RedirectServiceImpl::validate_expires_at(redirect.expires_at),
+            RedirectServiceImpl::validate_max_clicks(redirect.max_clicks),
+            RedirectServiceImpl::validate_activation_window(
+                redirect.active_from,
+                redirect.active_until,
+            ),
+            RedirectServiceImpl::validate_fallback_url(redirect.fallback_url.as_deref()),
+            RedirectServiceImpl::validate_query_params(&redirect.query_params),
+            RedirectServiceImpl::validate_password(redirect.password.as_deref()),
+            RedirectServiceImpl::validate_import_protection(
+                redirect.protected,
+                redirect.password.as_deref(),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|res| match res {
+            Err(DbServiceError::PayloadValidationError(on_item, errors)) => {
+                Some(ValidationErrorResponse { on_item, errors })
+            }
+            _ => None,
+        })
+        .collect()
+    }
+
+    fn page_query(query: &RedirectListQueryDTO) -> Result<RedirectPageQuery, DbServiceError> {
+        let limit = query.limit.unwrap_or(RedirectPageQuery::DEFAULT_LIMIT);
+        if !(1..=RedirectPageQuery::MAX_LIMIT).contains(&limit) {
+            return Err(DbServiceError::PayloadValidationError(
+                "limit".to_string(),
+                vec![format!(
+                    "has to be between 1 and {}",
+                    RedirectPageQuery::MAX_LIMIT
+                )],
+            ));
+        }
+        let sort = query.sort.unwrap_or_default();
+        let order = query.order.unwrap_or_default();
+        let after = query
+            .cursor
+            .as_deref()
+            .map(|c| Self::decode_cursor(c, sort, order))
+            .transpose()?;
+        Ok(RedirectPageQuery {
+            limit,
+            sort,
+            order,
+            search: query.q.clone().filter(|q| !q.is_empty()),
+            tag: query.tag.clone(),
+            after,
+            deleted: false,
+        })
+    }
+
+    async fn read_full_redirect_list(
+        &self,
+        page_query: RedirectPageQuery,
+    ) -> Result<FullRedirectListDTO, DbServiceError> {
+        let page = self
+            .repo
+            .read_all_redirects(&page_query)
+            .await
+            .map_err(DbServiceError::from)?;
+        Ok(FullRedirectListDTO {
+            redirects: self.with_details(page.redirects).await?,
+            next_cursor: page
+                .next_cursor
+                .map(|c| Self::encode_cursor(&c, page_query.sort, page_query.order)),
+        })
+    }
+
+    async fn read_user_redirect_list(
+        &self,
+        user_id: &str,
+        page_query: RedirectPageQuery,
+    ) -> Result<RedirectListDTO, DbServiceError> {
+        let page = self
+            .repo
+            .read_all_redirects_by_user_id(user_id, &page_query)
+            .await
+            .map_err(DbServiceError::from)?;
+        Ok(RedirectListDTO {
+            redirects: self
+                .with_details(page.redirects)
+                .await?
+                .into_iter()
+                .map(std::convert::Into::into)
+                .collect(),
+            next_cursor: page
+                .next_cursor
+                .map(|c| Self::encode_cursor(&c, page_query.sort, page_query.order)),
+        })
+    }
+
+    fn encode_cursor(cursor: &RedirectCursor, sort: RedirectSortKey, order: SortOrder) -> String {
+        let value = match &cursor.value {
+            CursorValue::Text(value) => value.clone(),
+            CursorValue::Integer(value) => value.to_string(),
+        };
+        general_purpose::URL_SAFE_NO_PAD.encode(format!("{sort:?}|{order:?}|{}|{value}", cursor.id))
+    }
+
+    fn decode_cursor(
+        cursor: &str,
+        sort: RedirectSortKey,
+        order: SortOrder,
+    ) -> Result<RedirectCursor, DbServiceError> {
+        let invalid = || {
+            DbServiceError::PayloadValidationError(
+                "cursor".to_string(),
+                vec!["is invalid or doesn't match sort and order".to_owned()],
+            )
+        };
+        let decoded = general_purpose::URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(invalid)?;
+        let parts: Vec<&str> = decoded.splitn(4, '|').collect();
+        let [cursor_sort, cursor_order, id, value] = parts[..] else {
+            return Err(invalid());
+        };
+        if cursor_sort != format!("{sort:?}") || cursor_order != format!("{order:?}") {
+            return Err(invalid());
+        }
+        let value = match sort {
+            RedirectSortKey::Alias => CursorValue::Text(value.to_owned()),
+            _ => CursorValue::Integer(value.parse().map_err(|_| invalid())?),
+        };
+        Ok(RedirectCursor {
+            value,
+            id: id.to_owned(),
+        })
+    }
+
+    fn bulk_change(operation: &BulkOperationDTO) -> Result<BulkChange<'_>, DbServiceError> {
+        let required = |item: &str| {
+            DbServiceError::PayloadValidationError(
+                item.to_string(),
+                vec![format!("is required for {:?}", operation.action)],
+            )
+        };
+        match (operation.aliases.as_ref(), operation.filter.as_ref()) {
+            (Some(aliases), None) if aliases.len() > Self::MAX_BULK_ITEMS => {
+                return Err(DbServiceError::PayloadValidationError(
+                    "aliases".to_string(),
+                    vec![format!(
+                        "at most {} redirects can be changed at once",
+                        Self::MAX_BULK_ITEMS
+                    )],
+                ));
+            }
+            (Some(_), None) => {}
+            (None, Some(filter)) if Self::bulk_filter_is_empty(filter) => {
+                return Err(DbServiceError::PayloadValidationError(
+                    "filter".to_string(),
+                    vec!["has to contain at least one of q or tag".to_owned()],
+                ));
+            }
+            (None, Some(_)) => {}
+            _ => {
+                return Err(DbServiceError::PayloadValidationError(
+                    "aliases".to_string(),
+                    vec!["either aliases or filter has to be supplied".to_owned()],
+                ));
+            }
+        }
+        match operation.action {
+            BulkAction::Delete => Ok(BulkChange::Delete),
+            BulkAction::AddTag => {
+                let tag = operation.tag.as_deref().ok_or_else(|| required("tag"))?;
+                TagServiceImpl::validate_tag(tag)?;
+                Ok(BulkChange::AddTag(tag))
+            }
+            BulkAction::RemoveTag => {
+                let tag = operation.tag.as_deref().ok_or_else(|| required("tag"))?;
+                Ok(BulkChange::RemoveTag(tag))
+            }
+            BulkAction::Repoint => {
+                let url = operation.url.as_deref().ok_or_else(|| required("url"))?;
+                RedirectServiceImpl::validate_url(url)?;
+                Ok(BulkChange::Repoint(url))
+            }
+        }
+    }
+
+    fn bulk_filter_is_empty(filter: &BulkFilterDTO) -> bool {
+        filter.q.as_deref().is_none_or(str::is_empty)
+            && filter.tag.as_deref().is_none_or(str::is_empty)
+    }
+
+    async fn read_filtered_redirects(
+        &self,
+        filter: &BulkFilterDTO,
+        owner: Option<&str>,
+    ) -> Result<Vec<Redirect>, DbServiceError> {
+        let mut page_query = RedirectPageQuery {
+            limit: RedirectPageQuery::MAX_LIMIT,
+            search: filter.q.clone().filter(|q| !q.is_empty()),
+            tag: filter.tag.clone().filter(|t| !t.is_empty()),
+            ..Default::default()
+        };
+        let mut redirects = Vec::new();
+        loop {
+            let page = match owner {
+                Some(owner) => {
+                    self.repo
+                        .read_all_redirects_by_user_id(owner, &page_query)
+                        .await?
+                }
+                None => self.repo.read_all_redirects(&page_query).await?,
+            };
+            redirects.extend(page.redirects);
+            if redirects.len() > Self::MAX_BULK_ITEMS {
+                return Err(DbServiceError::PayloadValidationError(
+                    "filter".to_string(),
+                    vec![format!(
+                        "matches more than {} redirects",
+                        Self::MAX_BULK_ITEMS
+                    )],
+                ));
+            }
+            match page.next_cursor {
+                Some(cursor) => page_query.after = Some(cursor),
+                None => return Ok(redirects),
+            }
+        }
+    }
+
+    async fn apply_bulk_change(
+        &self,
+        change: &BulkChange<'_>,
+        redirect: &Redirect,
+        user_id: &str,
+    ) -> Result<(), DbServiceError> {
+        let res = match change {
+            BulkChange::Delete => self.repo.delete_redirect_by_id(&redirect.id).await?,
+            BulkChange::AddTag(tag) => {
+                self.tag_repo.add_tag_to_redirect(&redirect.id, tag).await?;
+                return Ok(());
+            }
+            BulkChange::RemoveTag(tag) => {
+                self.tag_repo
+                    .remove_tag_from_redirect(&redirect.id, tag)
+                    .await?
+            }
+            BulkChange::Repoint(url) => {
+                // the placeholders of template redirects must stay out of scheme and host
+                Self::validate_template(url, redirect.template, redirect.passthrough)?;
+                self.repo
+                    .update_redirect_url_by_id(&redirect.id, url, user_id)
+                    .await?
+            }
+        };
+        if res == 0 {
+            return Err(DbServiceError::NotFoundError);
+        }
+        Ok(())
+    }
+
+    async fn bulk_update(
+        &self,
+        operation: &BulkOperationDTO,
+        user_id: &str,
+        owner: Option<&str>,
+    ) -> Result<BulkResultDTO, DbServiceError> {
+        let change = Self::bulk_change(operation)?;
+        let targets: Vec<(String, Result<Redirect, DbServiceError>)> =
+            match (operation.aliases.as_ref(), operation.filter.as_ref()) {
+                (Some(aliases), _) => {
+                    let mut targets = Vec::with_capacity(aliases.len());
+                    for alias in aliases {
+                        let redirect = self.repo.read_redirect_by_alias(alias).await;
+                        targets.push((alias.clone(), redirect.map_err(DbServiceError::from)));
+                    }
+                    targets
+                }
+                (None, Some(filter)) => self
+                    .read_filtered_redirects(filter, owner)
+                    .await?
+                    .into_iter()
+                    .map(|r| (r.alias.clone(), Ok(r)))
+                    .collect(),
+                (None, None) => Vec::new(),
+            };
+
+        let mut items = Vec::with_capacity(targets.len());
+        for (alias, redirect) in targets {
+            let result = match redirect {
+                Ok(redirect) if owner.is_some_and(|owner| owner != redirect.owner) => {
+                    Err(DbServiceError::PermissionError(
+                        "User is not authorized to change redirect".to_owned(),
+                    ))
+                }
+                Ok(redirect) => self.apply_bulk_change(&change, &redirect, user_id).await,
+                Err(e) => Err(e),
+            };
+            let (status, error) = match result {
+                Ok(()) => (BulkItemStatus::Ok, None),
+                Err(DbServiceError::NotFoundError) => (BulkItemStatus::NotFound, None),
+                Err(DbServiceError::PermissionError(_)) => (BulkItemStatus::Forbidden, None),
+                Err(e) => (BulkItemStatus::Failed, Some(e.to_string())),
+            };
+            items.push(BulkItemResultDTO {
+                alias,
+                status,
+                error,
+            });
+        }
+
+        let succeeded = items
+            .iter()
+            .filter(|i| i.status == BulkItemStatus::Ok)
+            .count();
+        Ok(BulkResultDTO {
+            action: operation.action,
+            succeeded,
+            failed: items.len() - succeeded,
+            items,
+        })
+    }
+
+    // The requested path is appended to the path of the target url with exactly one slash in
+    // between, the requested query is appended to the query of the target url. A fragment of the
+    // target url is kept at the end.
+    fn passthrough_url(url: &str, path: Option<&str>, query: Option<&str>) -> String {
+        let (url, fragment) = url
+            .split_once('#')
+            .map_or((url, None), |(url, fragment)| (url, Some(fragment)));
+        let (base, target_query) = url
+            .split_once('?')
+            .map_or((url, None), |(base, query)| (base, Some(query)));
+        let mut joined = base.to_owned();
+        if let Some(path) = path {
+            if !joined.ends_with('/') {
+                joined.push('/');
+            }
+            joined.push_str(path.trim_start_matches('/'));
+        }
+        let query: Vec<&str> = [target_query, query]
+            .into_iter()
+            .flatten()
+            .filter(|q| !q.is_empty())
+            .collect();
+        if !query.is_empty() {
+            joined.push('?');
+            joined.push_str(&query.join("&"));
+        }
+        if let Some(fragment) = fragment {
+            joined.push('#');
+            joined.push_str(fragment);
+        }
+        joined
+    }
+
+    fn validate_template(
+        url: &str,
+        template: bool,
+        passthrough: bool,
+    ) -> Result<(), DbServiceError> {
+        if !template {
+            return Ok(());
+        }
+        if passthrough {
+            return Err(DbServiceError::PayloadValidationError(
+                "template".to_string(),
+                vec!["can not be combined with passthrough".to_owned()],
+            ));
+        }
+        PayloadValidator::new(url)
+            .url_template()
+            .validate()
+            .map_err(|e| DbServiceError::PayloadValidationError("url".to_string(), e))
+    }
+
+    // Placeholders are replaced with the path segments after the alias ({1}, {2}, ...), the
+    // whole path ({path}) or the query string ({query}). None if a numbered segment is missing.
+    fn fill_template(url: &str, path: Option<&str>, query: Option<&str>) -> Option<String> {
+        let segments: Vec<&str> = path
+            .unwrap_or_default()
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect();
+        let mut filled = String::with_capacity(url.len());
+        let mut rest = url;
+        while let Some(start) = rest.find('{') {
+            let end = start + rest[start..].find('}')?;
+            filled.push_str(&rest[..start]);
+            match &rest[start + 1..end] {
+                "path" => filled.push_str(&segments.join("/")),
+                "query" => filled.push_str(query.unwrap_or_default()),
+                position => {
+                    let index = position.parse::<usize>().ok()?.checked_sub(1)?;
+                    filled.push_str(segments.get(index)?);
+                }
+            }
+            rest = &rest[end + 1..];
+        }
+        filled.push_str(rest);
+        Some(filled)
+    }
+
+    fn validate_query_params(params: &BTreeMap<String, String>) -> Result<(), DbServiceError> {
+        let mut errors = Vec::new();
+        if params.len() > Self::MAX_QUERY_PARAMS {
+            errors.push(format!(
+                "at most {} parameters are allowed",
+                Self::MAX_QUERY_PARAMS
+            ));
+        }
+        for (name, value) in params {
+            if let Err(e) = PayloadValidator::new(name)
+                .not_empty()
+                .max_length(100)
+                .validate()
+            {
+                errors.extend(e.into_iter().map(|e| format!("name {e}")));
+            }
+            if let Err(e) = PayloadValidator::new(value).max_length(500).validate() {
+                errors.extend(e.into_iter().map(|e| format!("value of {name}: {e}")));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(DbServiceError::PayloadValidationError(
+                "query_params".to_string(),
+                errors,
+            ));
+        }
+        Ok(())
+    }
+
+    fn encode_query_component(value: &str) -> String {
+        let mut encoded = String::with_capacity(value.len());
+        for byte in value.bytes() {
+            if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+                encoded.push(char::from(byte));
+            } else {
+                encoded.push_str(&format!("%{byte:02X}"));
+            }
+        }
+        encoded
+    }
+
+    // Parameters the url already contains win over the configured ones, so a link can still
+    // override e.g. the campaign via passthrough.
+    fn append_query_params(url: &str, params: &BTreeMap<String, String>) -> String {
+        let (base, fragment) = url
+            .split_once('#')
+            .map_or((url, None), |(base, fragment)| (base, Some(fragment)));
+        let existing: Vec<&str> = base
+            .split_once('?')
+            .map(|(_, query)| {
+                query
+                    .split('&')
+                    .map(|pair| pair.split_once('=').map_or(pair, |(name, _)| name))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut joined = base.to_owned();
+        for (name, value) in params {
+            let name = Self::encode_query_component(name);
+            if existing.contains(&name.as_str()) {
+                continue;
+            }
+            if !joined.ends_with(['?', '&']) {
+                joined.push(if joined.contains('?') { '&' } else { '?' });
+            }
+            joined.push_str(&name);
+            joined.push('=');
+            joined.push_str(&Self::encode_query_component(value));
+        }
+        if let Some(fragment) = fragment {
+            joined.push('#');
+            joined.push_str(fragment);
+        }
+        joined
+    }
+
     fn validate_url(url: &str) -> Result<(), DbServiceError> {
         PayloadValidator::new(url)
             .not_empty()
@@ -44,51 +869,306 @@ impl RedirectServiceImpl {
 
 #[async_trait]
 impl RedirectService for RedirectServiceImpl {
-    async fn get_redirect(&self, alias: &str) -> Result<RedirectDTO, DbServiceError> {
-        let result = self
-            .repo
-            .read_redirect_by_alias(alias)
+    async fn get_redirect(
+        &self,
+        alias: &str,
+        path: Option<&str>,
+        query: Option<&str>,
+    ) -> Result<Redirect, DbServiceError> {
+        self.resolve_target(alias, path, query, true, None).await
+    }
+
+    async fn preview_redirect(
+        &self,
+        alias: &str,
+        path: Option<&str>,
+        query: Option<&str>,
+    ) -> Result<Redirect, DbServiceError> {
+        self.resolve_target(alias, path, query, false, None).await
+    }
+
+    async fn unlock_redirect(
+        &self,
+        alias: &str,
+        path: Option<&str>,
+        query: Option<&str>,
+        password: &str,
+        client: &str,
+        preview: bool,
+    ) -> Result<Redirect, DbServiceError> {
+        self.resolve_target(alias, path, query, !preview, Some((password, client)))
             .await
-            .map_err(DbServiceError::from)?;
-        Ok(result.into())
     }
 
-    async fn create_redirect(&self, redirect: &RedirectCreationDTO) -> Result<(), DbServiceError> {
-        RedirectServiceImpl::validate_alias(&redirect.redirect.alias)?;
+    async fn create_redirect(
+        &self,
+        redirect: &RedirectCreationDTO,
+    ) -> Result<RedirectDTO, DbServiceError> {
+        let generate_alias = redirect.redirect.alias.is_empty();
+        if !generate_alias {
+            RedirectServiceImpl::validate_alias(&redirect.redirect.alias)?;
+        }
         RedirectServiceImpl::validate_url(&redirect.redirect.url)?;
-        let redirect = Redirect {
+        RedirectServiceImpl::validate_template(
+            &redirect.redirect.url,
+            redirect.redirect.template,
+            redirect.redirect.passthrough,
+        )?;
+        RedirectServiceImpl::validate_expires_at(redirect.redirect.expires_at)?;
+        RedirectServiceImpl::validate_max_clicks(redirect.redirect.max_clicks)?;
+        RedirectServiceImpl::validate_activation_window(
+            redirect.redirect.active_from,
+            redirect.redirect.active_until,
+        )?;
+        RedirectServiceImpl::validate_fallback_url(redirect.redirect.fallback_url.as_deref())?;
+        RedirectServiceImpl::validate_query_params(&redirect.redirect.query_params)?;
+        RedirectServiceImpl::validate_password(redirect.redirect.password.as_deref())?;
+        let now = now_unix();
+        let mut redirect = Redirect {
             id: Uuid::new_v4().to_string(),
             alias: redirect.redirect.alias.clone(),
             url: redirect.redirect.url.clone(),
             owner: redirect.owner.clone(),
+            expires_at: redirect.redirect.expires_at,
+            max_clicks: redirect.redirect.max_clicks,
+            remaining_clicks: redirect.redirect.max_clicks,
+            redirect_type: redirect.redirect.redirect_type,
+            passthrough: redirect.redirect.passthrough,
+            template: redirect.redirect.template,
+            query_params: redirect.redirect.query_params.clone(),
+            case_insensitive: redirect.redirect.case_insensitive,
+            preview: redirect.redirect.preview,
+            pwhash: Self::hash_password(redirect.redirect.password.as_deref()).await?,
+            active_from: redirect.redirect.active_from,
+            active_until: redirect.redirect.active_until,
+            fallback_url: redirect.redirect.fallback_url.clone(),
+            created_at: now,
+            updated_at: now,
+            updated_by: Some(redirect.owner.clone()),
+            ..Default::default()
         };
+        redirect.state = redirect.state_at(now);
+        if !generate_alias {
+            self.repo.create_redirect(&redirect).await?;
+            return Ok(redirect.into());
+        }
 
-        self.repo
-            .create_redirect(&redirect)
-            .await
-            .map_err(DbServiceError::from)
+        for _ in 0..Self::MAX_ALIAS_ATTEMPTS {
+            redirect.alias = Self::generate_alias(&self.alias_config);
+            if RedirectServiceImpl::validate_alias(&redirect.alias).is_err() {
+                continue;
+            }
+            match self.repo.create_redirect(&redirect).await {
+                Ok(()) => return Ok(redirect.into()),
+                Err(e) => match DbServiceError::from(e) {
+                    DbServiceError::ResourceConflict => {}
+                    e => return Err(e),
+                },
+            }
+        }
+        Err(DbServiceError::ResourceConflict)
     }
 
-    async fn get_all_redirects(&self) -> Result<FullRedirectListDTO, DbServiceError> {
-        let redirects = self
-            .repo
-            .read_all_redirects()
-            .await
-            .map_err(DbServiceError::from)?;
-        Ok(FullRedirectListDTO { redirects })
+    async fn import_redirects(
+        &self,
+        owner: &str,
+        format: ImportFormat,
+        data: &[u8],
+        dry_run: bool,
+    ) -> Result<ImportReportDTO, DbServiceError> {
+        let rows = Self::parse_import(format, data)?;
+        if rows.len() > Self::MAX_IMPORT_ROWS {
+            return Err(DbServiceError::PayloadValidationError(
+                "rows".to_string(),
+                vec![format!(
+                    "at most {} rows can be imported at once",
+                    Self::MAX_IMPORT_ROWS
+                )],
+            ));
+        }
+
+        let now = now_unix();
+        let mut results = Vec::with_capacity(rows.len());
+        let mut redirects = Vec::new();
+        for (i, row) in rows.into_iter().enumerate() {
+            let (alias, errors) = match row {
+                Ok(redirect) => {
+                    let errors = Self::validation_errors(&redirect);
+                    if errors.is_empty() {
+                        redirects.push((
+                            results.len(),
+                            redirect.password,
+                            Redirect {
+                                id: Uuid::new_v4().to_string(),
+                                alias: redirect.alias.clone(),
+                                url: redirect.url,
+                                owner: owner.to_owned(),
+                                expires_at: redirect.expires_at,
+                                max_clicks: redirect.max_clicks,
+                                remaining_clicks: redirect.max_clicks,
+                                redirect_type: redirect.redirect_type,
+                                passthrough: redirect.passthrough,
+                                template: redirect.template,
+                                query_params: redirect.query_params,
+                                case_insensitive: redirect.case_insensitive,
+                                preview: redirect.preview,
+                                active_from: redirect.active_from,
+                                active_until: redirect.active_until,
+                                fallback_url: redirect.fallback_url,
+                                enabled: redirect.enabled,
+                                created_at: now,
+                                updated_at: now,
+                                updated_by: Some(owner.to_owned()),
+                                ..Default::default()
+                            },
+                        ));
+                    }
+                    (redirect.alias, errors)
+                }
+                Err(e) => (
+                    String::new(),
+                    vec![ValidationErrorResponse {
+                        on_item: "row".to_string(),
+                        errors: vec![e],
+                    }],
+                ),
+            };
+            results.push(ImportRowResultDTO {
+                row: i + 1,
+                alias,
+                status: ImportRowStatus::Invalid,
+                errors,
+            });
+        }
+
+        // conflicts are filtered before the passwords of the remaining rows are hashed
+        let checked: Vec<Redirect> = redirects.iter().map(|(_, _, r)| r.clone()).collect();
+        let available = self.repo.create_redirects(&checked, true).await?;
+        let mut creatable = Vec::with_capacity(redirects.len());
+        for ((position, password, redirect), available) in redirects.into_iter().zip(available) {
+            if let Some(result) = results.get_mut(position) {
+                result.status = if available {
+                    ImportRowStatus::Created
+                } else {
+                    ImportRowStatus::Conflict
+                };
+            }
+            if available && !dry_run {
+                creatable.push((position, password, redirect));
+            }
+        }
+
+        let hashed: Vec<_> = stream::iter(creatable)
+            .map(|(position, password, redirect)| async move {
+                let pwhash = Self::hash_password(password.as_deref()).await;
+                (position, pwhash, redirect)
+            })
+            .buffered(Self::IMPORT_HASH_CONCURRENCY)
+            .collect()
+            .await;
+        let mut positions = Vec::with_capacity(hashed.len());
+        let mut redirects = Vec::with_capacity(hashed.len());
+        for (position, pwhash, redirect) in hashed {
+            match pwhash {
+                Ok(pwhash) => {
+                    positions.push(position);
+                    redirects.push(Redirect { pwhash, ..redirect });
+                }
+                Err(e) => {
+                    if let Some(result) = results.get_mut(position) {
+                        result.status = ImportRowStatus::Invalid;
+                        result.errors.push(ValidationErrorResponse {
+                            on_item: "password".to_string(),
+                            errors: vec![e.to_string()],
+                        });
+                    }
+                }
+            }
+        }
+
+        // rows taken since the check are reported as conflicts
+        let created = self.repo.create_redirects(&redirects, false).await?;
+        for (position, created) in positions.into_iter().zip(created) {
+            if let Some(result) = results.get_mut(position)
+                && !created
+            {
+                result.status = ImportRowStatus::Conflict;
+            }
+        }
+
+        let count = |status| results.iter().filter(|r| r.status == status).count();
+        Ok(ImportReportDTO {
+            dry_run,
+            created: count(ImportRowStatus::Created),
+            conflicts: count(ImportRowStatus::Conflict),
+            invalid: count(ImportRowStatus::Invalid),
+            rows: results,
+        })
+    }
+
+    async fn get_all_redirects(
+        &self,
+        query: &RedirectListQueryDTO,
+    ) -> Result<FullRedirectListDTO, DbServiceError> {
+        self.read_full_redirect_list(Self::page_query(query)?).await
     }
 
     async fn get_all_user_redirects(
         &self,
         user_id: &str,
+        query: &RedirectListQueryDTO,
     ) -> Result<RedirectListDTO, DbServiceError> {
-        self.repo
-            .read_all_redirects_by_user_id(user_id)
+        self.read_user_redirect_list(user_id, Self::page_query(query)?)
             .await
-            .map_err(DbServiceError::from)
-            .map(|r| RedirectListDTO {
-                redirects: r.into_iter().map(std::convert::Into::into).collect(),
-            })
+    }
+
+    async fn get_all_trashed_redirects(
+        &self,
+        query: &RedirectListQueryDTO,
+    ) -> Result<FullRedirectListDTO, DbServiceError> {
+        let page_query = RedirectPageQuery {
+            deleted: true,
+            ..Self::page_query(query)?
+        };
+        self.read_full_redirect_list(page_query).await
+    }
+
+    async fn get_user_trashed_redirects(
+        &self,
+        user_id: &str,
+        query: &RedirectListQueryDTO,
+    ) -> Result<RedirectListDTO, DbServiceError> {
+        let page_query = RedirectPageQuery {
+            deleted: true,
+            ..Self::page_query(query)?
+        };
+        self.read_user_redirect_list(user_id, page_query).await
+    }
+
+    async fn restore_redirect_by_id(&self, id: &str) -> Result<(), DbServiceError> {
+        let res = self.repo.restore_redirect_by_id(id).await?;
+        if res == 0 {
+            return Err(DbServiceError::NotFoundError);
+        }
+        Ok(())
+    }
+
+    async fn restore_user_redirect(
+        &self,
+        alias: &str,
+        user_id: &str,
+    ) -> Result<(), DbServiceError> {
+        let redirect = self.repo.read_deleted_redirect_by_alias(alias).await?;
+        if redirect.owner != user_id {
+            return Err(DbServiceError::PermissionError(
+                "User is not authorized to restore redirect".to_owned(),
+            ));
+        }
+        let res = self.repo.restore_redirect_by_id(&redirect.id).await?;
+        if res == 0 {
+            return Err(DbServiceError::NotFoundError);
+        }
+        Ok(())
     }
 
     async fn delete_redirect_by_id(&self, id: &str) -> Result<(), DbServiceError> {
@@ -121,21 +1201,1251 @@ impl RedirectService for RedirectServiceImpl {
     async fn update_redirect(
         &self,
         alias: &str,
-        redirect: &UpdateUrlDTO,
+        redirect: &UpdateRedirectDTO,
         user_id: &str,
-    ) -> Result<(), DbServiceError> {
-        RedirectServiceImpl::validate_url(&redirect.url)?;
+    ) -> Result<RedirectDTO, DbServiceError> {
+        if let Some(new_alias) = &redirect.alias {
+            RedirectServiceImpl::validate_alias(new_alias)?;
+        }
+        if let Some(url) = &redirect.url {
+            RedirectServiceImpl::validate_url(url)?;
+        }
+        if let Some(query_params) = &redirect.query_params {
+            RedirectServiceImpl::validate_query_params(query_params)?;
+        }
+        RedirectServiceImpl::validate_fallback_url(
+            redirect.fallback_url.as_ref().and_then(Option::as_deref),
+        )?;
+        let pwhash = match redirect.password.as_ref() {
+            Some(Some(password)) => {
+                RedirectServiceImpl::validate_password(Some(password))?;
+                Some(Self::hash_password(Some(password)).await?)
+            }
+            Some(None) => Some(None),
+            None => None,
+        };
+        // the merged redirect is validated inside the update transaction
+        let validate = |updated: &Redirect| {
+            RedirectServiceImpl::validate_template(
+                &updated.url,
+                updated.template,
+                updated.passthrough,
+            )?;
+            RedirectServiceImpl::validate_activation_window(
+                updated.active_from,
+                updated.active_until,
+            )
+        };
         let res = self
             .repo
-            .update_redirect_by_alias(alias, redirect, user_id)
+            .update_redirect_by_alias(
+                alias,
+                redirect,
+                pwhash.as_ref().map(Option::as_deref),
+                user_id,
+                &validate,
+            )
             .await
-            .map_err(DbServiceError::from)?;
+            .map_err(|e| match e {
+                RedirectUpdateError::UnknownOwner => DbServiceError::PayloadValidationError(
+                    "owner".to_string(),
+                    vec!["user does not exist".to_owned()],
+                ),
+                RedirectUpdateError::Invalid(e) => e,
+                RedirectUpdateError::Db(e) => DbServiceError::from(e),
+            })?;
         if res == 0 {
             self.repo.read_redirect_by_alias(alias).await?;
             return Err(DbServiceError::PermissionError(
                 "User is not authorized to update redirect".to_owned(),
             ));
         }
+        let updated = self
+            .repo
+            .read_redirect_by_alias(redirect.alias.as_deref().unwrap_or(alias))
+            .await?;
+        let updated = self.with_details(vec![updated]).await?;
+        Ok(updated.into_iter().next().unwrap_or_default().into())
+    }
+
+    async fn add_secondary_alias(
+        &self,
+        alias: &str,
+        secondary_alias: &str,
+        user_id: &str,
+    ) -> Result<(), DbServiceError> {
+        RedirectServiceImpl::validate_alias(secondary_alias)?;
+        let redirect = self.read_owned_redirect(alias, user_id).await?;
+        let existing = self
+            .repo
+            .read_secondary_aliases_by_redirect_ids(std::slice::from_ref(&redirect.id))
+            .await?;
+        if existing.len() >= Self::MAX_SECONDARY_ALIASES {
+            return Err(DbServiceError::PayloadValidationError(
+                "alias".to_owned(),
+                vec![format!(
+                    "a redirect can't have more than {} secondary aliases",
+                    Self::MAX_SECONDARY_ALIASES
+                )],
+            ));
+        }
+        self.repo
+            .create_secondary_alias(&redirect.id, secondary_alias, now_unix())
+            .await?;
+        Ok(())
+    }
+
+    async fn remove_secondary_alias(
+        &self,
+        alias: &str,
+        secondary_alias: &str,
+        user_id: &str,
+    ) -> Result<(), DbServiceError> {
+        let redirect = self.read_owned_redirect(alias, user_id).await?;
+        let res = self
+            .repo
+            .delete_secondary_alias(&redirect.id, secondary_alias)
+            .await?;
+        if res == 0 {
+            return Err(DbServiceError::NotFoundError);
+        }
+        Ok(())
+    }
+
+    async fn get_redirect_history(
+        &self,
+        alias: &str,
+        user_id: &str,
+    ) -> Result<RedirectHistoryDTO, DbServiceError> {
+        let redirect = self.read_owned_redirect(alias, user_id).await?;
+        let history = self.repo.read_redirect_history(&redirect.id).await?;
+        Ok(RedirectHistoryDTO {
+            alias: redirect.alias,
+            history,
+        })
+    }
+
+    async fn rollback_redirect(
+        &self,
+        alias: &str,
+        entry_id: i64,
+        user_id: &str,
+    ) -> Result<RedirectDTO, DbServiceError> {
+        let redirect = self.read_owned_redirect(alias, user_id).await?;
+        let entry = self
+            .repo
+            .read_redirect_history_entry(&redirect.id, entry_id)
+            .await?;
+        RedirectServiceImpl::validate_template(
+            &entry.old_url,
+            redirect.template,
+            redirect.passthrough,
+        )?;
+        // the rollback is recorded in the history like any other url change
+        self.repo
+            .update_redirect_url_by_id(&redirect.id, &entry.old_url, user_id)
+            .await?;
+        let updated = self.repo.read_redirect_by_alias(&redirect.alias).await?;
+        let updated = self.with_details(vec![updated]).await?;
+        Ok(updated.into_iter().next().unwrap_or_default().into())
+    }
+
+    async fn set_redirect_enabled(
+        &self,
+        alias: &str,
+        enabled: bool,
+        user_id: &str,
+    ) -> Result<(), DbServiceError> {
+        let redirect = self.read_owned_redirect(alias, user_id).await?;
+        if redirect.locked {
+            return Err(DbServiceError::PermissionError(
+                "Redirect was disabled by an admin".to_owned(),
+            ));
+        }
+        self.repo
+            .update_redirect_enabled_by_id(&redirect.id, enabled, false, user_id)
+            .await?;
+        Ok(())
+    }
+
+    async fn set_redirect_enabled_by_id(
+        &self,
+        id: &str,
+        enabled: bool,
+        admin_id: &str,
+    ) -> Result<(), DbServiceError> {
+        // a redirect disabled by an admin stays disabled until an admin enables it again
+        let res = self
+            .repo
+            .update_redirect_enabled_by_id(id, enabled, !enabled, admin_id)
+            .await?;
+        if res == 0 {
+            return Err(DbServiceError::NotFoundError);
+        }
         Ok(())
     }
+
+    async fn bulk_update_redirects(
+        &self,
+        operation: &BulkOperationDTO,
+        user_id: &str,
+    ) -> Result<BulkResultDTO, DbServiceError> {
+        self.bulk_update(operation, user_id, None).await
+    }
+
+    async fn bulk_update_user_redirects(
+        &self,
+        operation: &BulkOperationDTO,
+        user_id: &str,
+    ) -> Result<BulkResultDTO, DbServiceError> {
+        self.bulk_update(operation, user_id, Some(user_id)).await
+    }
+
+    async fn get_redirect_stats(
+        &self,
+        alias: &str,
+        user_id: &str,
+    ) -> Result<RedirectStatsDTO, DbServiceError> {
+        let redirect = self.repo.read_redirect_by_alias(alias).await?;
+        if redirect.owner != user_id {
+            return Err(DbServiceError::PermissionError(
+                "User is not authorized to view redirect stats".to_owned(),
+            ));
+        }
+        let last_clicked_at = self
+            .click_repo
+            .read_last_click_by_redirect_id(&redirect.id)
+            .await?;
+        Ok(RedirectStatsDTO {
+            alias: redirect.alias,
+            clicks: redirect.clicks,
+            last_clicked_at,
+        })
+    }
+
+    async fn resolve_alias_collisions(&self) -> Result<Vec<AliasCollision>, DbServiceError> {
+        Ok(self.repo.resolve_alias_collisions().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use sqlx::SqlitePool;
+
+    use crate::{
+        AliasConfig,
+        data::{ClickRepoSqliteImpl, RedirectRepo, RedirectRepoSqliteImpl, TagRepoSqliteImpl},
+        model::{
+            BulkAction, BulkFilterDTO, BulkItemStatus, BulkOperationDTO, CursorValue, ImportFormat,
+            ImportRowStatus, Redirect, RedirectCursor, RedirectListQueryDTO, RedirectSortKey,
+            RedirectState, RedirectType, SortOrder, UpdateRedirectDTO,
+        },
+        now_unix,
+        service::{DbServiceError, RedirectService, redirect_service::RedirectServiceImpl},
+    };
+
+    #[test]
+    fn generate_alias_uses_configured_length_and_alphabet() {
+        let config = AliasConfig {
+            length: 12,
+            alphabet: vec!['a', 'b', '-'],
+            case_insensitive: false,
+        };
+        for _ in 0..100 {
+            let alias = RedirectServiceImpl::generate_alias(&config);
+            assert_eq!(alias.len(), 12);
+            assert!(alias.chars().all(|c| config.alphabet.contains(&c)));
+        }
+    }
+
+    #[test]
+    fn generate_alias_passes_alias_validation() {
+        let config = AliasConfig {
+            length: 6,
+            alphabet: "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz"
+                .chars()
+                .collect(),
+            case_insensitive: false,
+        };
+        let alias = RedirectServiceImpl::generate_alias(&config);
+        assert!(RedirectServiceImpl::validate_alias(&alias).is_ok());
+    }
+
+    #[test]
+    fn cursor_roundtrip_keeps_value_and_id() {
+        let cursor = RedirectCursor {
+            value: CursorValue::Text("some|alias".to_owned()),
+            id: "d64bcaad-8d86-48d2-b1f3-f1c03ac30fa3".to_owned(),
+        };
+        let encoded =
+            RedirectServiceImpl::encode_cursor(&cursor, RedirectSortKey::Alias, SortOrder::Desc);
+        let decoded =
+            RedirectServiceImpl::decode_cursor(&encoded, RedirectSortKey::Alias, SortOrder::Desc);
+        assert!(decoded.is_ok());
+        assert_eq!(decoded.unwrap(), cursor);
+    }
+
+    #[test]
+    fn cursor_with_different_sort_fails() {
+        let cursor = RedirectCursor {
+            value: CursorValue::Integer(42),
+            id: "d64bcaad-8d86-48d2-b1f3-f1c03ac30fa3".to_owned(),
+        };
+        let encoded =
+            RedirectServiceImpl::encode_cursor(&cursor, RedirectSortKey::Clicks, SortOrder::Asc);
+        let decoded =
+            RedirectServiceImpl::decode_cursor(&encoded, RedirectSortKey::Created, SortOrder::Asc);
+        assert!(matches!(
+            decoded,
+            Err(DbServiceError::PayloadValidationError(_, _))
+        ));
+        let garbage = RedirectServiceImpl::decode_cursor(
+            "not a cursor",
+            RedirectSortKey::Alias,
+            SortOrder::Asc,
+        );
+        assert!(garbage.is_err());
+    }
+
+    #[test]
+    fn page_query_with_invalid_limit_fails() {
+        for limit in [0, 1001] {
+            let query = RedirectListQueryDTO {
+                limit: Some(limit),
+                ..Default::default()
+            };
+            assert!(matches!(
+                RedirectServiceImpl::page_query(&query),
+                Err(DbServiceError::PayloadValidationError(_, _))
+            ));
+        }
+        let query = RedirectListQueryDTO::default();
+        let page_query = RedirectServiceImpl::page_query(&query).unwrap();
+        assert_eq!(page_query.limit, 100);
+    }
+
+    #[test]
+    fn parse_import_csv_with_optional_columns() {
+        let data = b"alias,url,max_clicks,redirect_type\ngh, https://github.com ,,308\ndocs,https://docs.rs,5,307\n";
+        let rows = RedirectServiceImpl::parse_import(ImportFormat::Csv, data).unwrap();
+        assert_eq!(rows.len(), 2);
+        let first = rows[0].as_ref().unwrap();
+        assert_eq!(first.alias, "gh");
+        assert_eq!(first.url, "https://github.com");
+        assert_eq!(first.max_clicks, None);
+        assert_eq!(first.redirect_type, RedirectType::PermanentRedirect);
+        assert_eq!(rows[1].as_ref().unwrap().max_clicks, Some(5));
+    }
+
+    #[test]
+    fn parse_import_json_reports_invalid_rows() {
+        let data = br#"[{"alias": "gh", "url": "https://github.com"}, {"alias": "docs"}]"#;
+        let rows = RedirectServiceImpl::parse_import(ImportFormat::Json, data).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].is_ok());
+        assert!(rows[1].is_err());
+
+        let not_an_array = RedirectServiceImpl::parse_import(ImportFormat::Json, b"{}");
+        assert!(matches!(
+            not_an_array,
+            Err(DbServiceError::PayloadValidationError(_, _))
+        ));
+    }
+
+    #[test]
+    fn parse_import_accepts_csv_export() {
+        let data = b"alias,url,expires_at,max_clicks,redirect_type,passthrough,template,query_params,tags,owner,clicks,created_at,updated_at\ngh,https://github.com,,,308,true,false,\"{\"\"utm_source\"\":\"\"news\"\"}\",code git,some_id_string,0,100,200\n";
+        let rows = RedirectServiceImpl::parse_import(ImportFormat::Csv, data).unwrap();
+        assert_eq!(rows.len(), 1);
+        let row = rows[0].as_ref().unwrap();
+        assert_eq!(row.alias, "gh");
+        assert!(row.passthrough);
+        assert_eq!(row.query_params.get("utm_source"), Some(&"news".to_owned()));
+        assert!(row.tags.is_empty());
+    }
+
+    fn bulk_operation(action: BulkAction) -> BulkOperationDTO {
+        BulkOperationDTO {
+            action,
+            aliases: Some(vec!["gh".to_owned()]),
+            filter: None,
+            tag: None,
+            url: None,
+        }
+    }
+
+    #[test]
+    fn bulk_change_requires_selection_and_arguments() {
+        assert!(RedirectServiceImpl::bulk_change(&bulk_operation(BulkAction::Delete)).is_ok());
+
+        let no_selection = BulkOperationDTO {
+            aliases: None,
+            ..bulk_operation(BulkAction::Delete)
+        };
+        let empty_filter = BulkOperationDTO {
+            aliases: None,
+            filter: Some(BulkFilterDTO {
+                q: Some(String::new()),
+                tag: None,
+            }),
+            ..bulk_operation(BulkAction::Delete)
+        };
+        let both = BulkOperationDTO {
+            filter: Some(BulkFilterDTO {
+                q: Some("gh".to_owned()),
+                tag: None,
+            }),
+            ..bulk_operation(BulkAction::Delete)
+        };
+        let too_many = BulkOperationDTO {
+            aliases: Some(vec!["gh".to_owned(); 1001]),
+            ..bulk_operation(BulkAction::Delete)
+        };
+        let invalid_tag = BulkOperationDTO {
+            tag: Some("not a tag".to_owned()),
+            ..bulk_operation(BulkAction::AddTag)
+        };
+        let invalid_url = BulkOperationDTO {
+            url: Some("not a url".to_owned()),
+            ..bulk_operation(BulkAction::Repoint)
+        };
+        for operation in [
+            no_selection,
+            empty_filter,
+            both,
+            too_many,
+            bulk_operation(BulkAction::AddTag),
+            bulk_operation(BulkAction::RemoveTag),
+            bulk_operation(BulkAction::Repoint),
+            invalid_tag,
+            invalid_url,
+        ] {
+            assert!(matches!(
+                RedirectServiceImpl::bulk_change(&operation),
+                Err(DbServiceError::PayloadValidationError(_, _))
+            ));
+        }
+    }
+
+    async fn setup_test_service(
+        case_insensitive: bool,
+    ) -> (Arc<RedirectRepoSqliteImpl>, RedirectServiceImpl) {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        sqlx::query(
+            "INSERT INTO users (id, name, pwhash, is_admin) VALUES
+            ('owner', 'owner', 'not_a_pw_hash', false), ('other', 'other', 'not_a_pw_hash', false);
+            INSERT INTO redirects (id, alias, alias_key, url, owner) VALUES
+            ('1', 'gh', 'gh', 'https://github.com', 'owner'),
+            ('2', 'docs', 'docs', 'https://docs.rs', 'other');",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let repo = Arc::new(RedirectRepoSqliteImpl::new(pool.clone()));
+        let service = RedirectServiceImpl::new(
+            repo.clone(),
+            Arc::new(ClickRepoSqliteImpl::new(pool.clone())),
+            Arc::new(TagRepoSqliteImpl::new(pool)),
+            AliasConfig {
+                length: 6,
+                alphabet: vec!['a', 'b'],
+                case_insensitive,
+            },
+        );
+        (repo, service)
+    }
+
+    #[tokio::test]
+    async fn bulk_update_enforces_ownership_per_item() {
+        let (repo, service) = setup_test_service(false).await;
+        let operation = BulkOperationDTO {
+            aliases: Some(vec![
+                "gh".to_owned(),
+                "docs".to_owned(),
+                "unknown".to_owned(),
+            ]),
+            url: Some("https://example.com".to_owned()),
+            ..bulk_operation(BulkAction::Repoint)
+        };
+
+        let result = service
+            .bulk_update_user_redirects(&operation, "owner")
+            .await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        let result = result.unwrap();
+        let statuses: Vec<_> = result.items.iter().map(|i| i.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                BulkItemStatus::Ok,
+                BulkItemStatus::Forbidden,
+                BulkItemStatus::NotFound
+            ]
+        );
+        assert_eq!((result.succeeded, result.failed), (1, 2));
+        let docs = repo.read_redirect_by_alias("docs").await.unwrap();
+        assert_eq!(docs.url, "https://docs.rs");
+
+        let result = service
+            .bulk_update_redirects(&operation, "admin")
+            .await
+            .unwrap();
+        assert_eq!(result.succeeded, 2);
+        let docs = repo.read_redirect_by_alias("docs").await.unwrap();
+        assert_eq!(docs.url, "https://example.com");
+        assert_eq!(docs.updated_by.as_deref(), Some("admin"));
+
+        let delete = BulkOperationDTO {
+            aliases: None,
+            filter: Some(BulkFilterDTO {
+                q: Some("example".to_owned()),
+                tag: None,
+            }),
+            ..bulk_operation(BulkAction::Delete)
+        };
+        let result = service
+            .bulk_update_user_redirects(&delete, "other")
+            .await
+            .unwrap();
+        assert_eq!(result.items.len(), 1);
+        assert_eq!(result.items[0].alias, "docs");
+        assert!(repo.read_redirect_by_alias("docs").await.is_err());
+        assert!(repo.read_redirect_by_alias("gh").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn bulk_repoint_validates_template_per_item() {
+        let (repo, service) = setup_test_service(false).await;
+        let update = UpdateRedirectDTO {
+            url: Some("https://jira.example.com/browse/{1}".to_owned()),
+            template: Some(true),
+            ..Default::default()
+        };
+        service
+            .update_redirect("gh", &update, "owner")
+            .await
+            .unwrap();
+        let operation = BulkOperationDTO {
+            aliases: Some(vec!["gh".to_owned(), "docs".to_owned()]),
+            url: Some("https://{1}/x".to_owned()),
+            ..bulk_operation(BulkAction::Repoint)
+        };
+
+        let result = service
+            .bulk_update_redirects(&operation, "admin")
+            .await
+            .unwrap();
+        assert_eq!(result.items[0].status, BulkItemStatus::Failed);
+        assert!(result.items[0].error.is_some());
+        // docs is no template, its url is taken literally
+        assert_eq!(result.items[1].status, BulkItemStatus::Ok);
+        let gh = repo.read_redirect_by_alias("gh").await.unwrap();
+        assert_eq!(gh.url, "https://jira.example.com/browse/{1}");
+    }
+
+    #[test]
+    fn fill_template_replaces_placeholders() {
+        let url = "https://jira.example.com/browse/{1}?q={query}";
+        assert_eq!(
+            RedirectServiceImpl::fill_template(url, Some("PROJ-123"), Some("x=1")),
+            Some("https://jira.example.com/browse/PROJ-123?q=x=1".to_owned())
+        );
+        assert_eq!(
+            RedirectServiceImpl::fill_template(url, Some("PROJ-123/ignored"), None),
+            Some("https://jira.example.com/browse/PROJ-123?q=".to_owned())
+        );
+        assert_eq!(
+            RedirectServiceImpl::fill_template(
+                "https://example.com/{2}/{1}/{path}",
+                Some("a//b%20c/"),
+                None
+            ),
+            Some("https://example.com/b%20c/a/a/b%20c".to_owned())
+        );
+        assert_eq!(RedirectServiceImpl::fill_template(url, None, None), None);
+        assert_eq!(
+            RedirectServiceImpl::fill_template("https://example.com/{2}", Some("a"), None),
+            None
+        );
+    }
+
+    #[test]
+    fn validate_template_rejects_passthrough_and_bad_placeholders() {
+        let url = "https://jira.example.com/browse/{1}";
+        assert!(RedirectServiceImpl::validate_template(url, true, false).is_ok());
+        assert!(RedirectServiceImpl::validate_template("https://{1}.com", false, true).is_ok());
+        for (url, passthrough) in [
+            (url, true),
+            ("https://{1}.com", false),
+            ("https://a.com/{x}", false),
+        ] {
+            assert!(matches!(
+                RedirectServiceImpl::validate_template(url, true, passthrough),
+                Err(DbServiceError::PayloadValidationError(_, _))
+            ));
+        }
+    }
+
+    #[test]
+    fn passthrough_url_joins_path_and_query() {
+        let cases = [
+            (
+                "https://docs.example.com",
+                Some("api/v2"),
+                None,
+                "https://docs.example.com/api/v2",
+            ),
+            (
+                "https://docs.example.com/",
+                Some("api/v2"),
+                None,
+                "https://docs.example.com/api/v2",
+            ),
+            (
+                "https://example.com/docs",
+                Some("api"),
+                None,
+                "https://example.com/docs/api",
+            ),
+            (
+                "https://example.com/docs/",
+                Some("//api/"),
+                None,
+                "https://example.com/docs/api/",
+            ),
+            (
+                "https://example.com/docs",
+                Some(""),
+                None,
+                "https://example.com/docs/",
+            ),
+            (
+                "https://example.com/docs",
+                None,
+                Some("x=1"),
+                "https://example.com/docs?x=1",
+            ),
+            (
+                "https://example.com/?lang=en",
+                Some("api"),
+                Some("x=1"),
+                "https://example.com/api?lang=en&x=1",
+            ),
+            (
+                "https://example.com/docs#top",
+                Some("a%20b"),
+                Some("x=1"),
+                "https://example.com/docs/a%20b?x=1#top",
+            ),
+            (
+                "https://example.com/docs?",
+                None,
+                Some(""),
+                "https://example.com/docs",
+            ),
+        ];
+        for (url, path, query, expected) in cases {
+            assert_eq!(
+                RedirectServiceImpl::passthrough_url(url, path, query),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn append_query_params_keeps_existing_parameters() {
+        let params = [
+            ("utm_campaign".to_owned(), "spring sale".to_owned()),
+            ("utm_source".to_owned(), "news".to_owned()),
+        ]
+        .into();
+        let cases = [
+            (
+                "https://example.com",
+                "https://example.com?utm_campaign=spring%20sale&utm_source=news",
+            ),
+            (
+                "https://example.com/?a=1#top",
+                "https://example.com/?a=1&utm_campaign=spring%20sale&utm_source=news#top",
+            ),
+            (
+                "https://example.com/?utm_source=blog",
+                "https://example.com/?utm_source=blog&utm_campaign=spring%20sale",
+            ),
+            (
+                "https://example.com/?",
+                "https://example.com/?utm_campaign=spring%20sale&utm_source=news",
+            ),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(
+                RedirectServiceImpl::append_query_params(url, &params),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn validate_query_params_fails() {
+        let valid = [("utm_source".to_owned(), String::new())].into();
+        assert!(RedirectServiceImpl::validate_query_params(&valid).is_ok());
+
+        let empty_name = [(String::new(), "news".to_owned())].into();
+        let long_value = [("utm_source".to_owned(), "x".repeat(501))].into();
+        let too_many = (0..21).map(|i| (format!("p{i}"), String::new())).collect();
+        for params in [empty_name, long_value, too_many] {
+            assert!(matches!(
+                RedirectServiceImpl::validate_query_params(&params),
+                Err(DbServiceError::PayloadValidationError(_, _))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn get_redirect_resolves_case_insensitive_aliases() {
+        let (_, service) = setup_test_service(true).await;
+        let redirect = service.get_redirect("GH", None, None).await;
+        dbg!(redirect.as_ref().err());
+        assert!(redirect.is_ok());
+        assert_eq!(redirect.unwrap().alias, "gh");
+
+        let update = UpdateRedirectDTO {
+            case_insensitive: Some(Some(false)),
+            ..Default::default()
+        };
+        service
+            .update_redirect("gh", &update, "owner")
+            .await
+            .unwrap();
+        assert!(matches!(
+            service.get_redirect("GH", None, None).await,
+            Err(DbServiceError::NotFoundError)
+        ));
+        assert!(service.get_redirect("gh", None, None).await.is_ok());
+
+        // an explicit null inherits the server default again
+        let update: UpdateRedirectDTO =
+            serde_json::from_str(r#"{"case_insensitive": null}"#).unwrap();
+        let updated = service
+            .update_redirect("gh", &update, "owner")
+            .await
+            .unwrap();
+        assert_eq!(updated.case_insensitive, None);
+        assert!(service.get_redirect("GH", None, None).await.is_ok());
+
+        let (_, service) = setup_test_service(false).await;
+        assert!(matches!(
+            service.get_redirect("Docs", None, None).await,
+            Err(DbServiceError::NotFoundError)
+        ));
+        let update = UpdateRedirectDTO {
+            case_insensitive: Some(Some(true)),
+            ..Default::default()
+        };
+        service
+            .update_redirect("docs", &update, "other")
+            .await
+            .unwrap();
+        assert!(service.get_redirect("Docs", None, None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn secondary_aliases_follow_the_redirect() {
+        let (_, service) = setup_test_service(false).await;
+        let result = service.add_secondary_alias("gh", "github", "owner").await;
+        dbg!(result.as_ref().err());
+        assert!(result.is_ok());
+        assert!(matches!(
+            service.add_secondary_alias("gh", "docs", "owner").await,
+            Err(DbServiceError::ResourceConflict)
+        ));
+        assert!(matches!(
+            service.add_secondary_alias("gh", "git", "other").await,
+            Err(DbServiceError::PermissionError(_))
+        ));
+        assert!(matches!(
+            service.add_secondary_alias("gh", "api", "owner").await,
+            Err(DbServiceError::PayloadValidationError(_, _))
+        ));
+
+        let update = UpdateRedirectDTO {
+            url: Some("https://github.com/rust-lang".to_owned()),
+            ..Default::default()
+        };
+        let updated = service
+            .update_redirect("gh", &update, "owner")
+            .await
+            .unwrap();
+        assert_eq!(updated.aliases, vec!["github".to_owned()]);
+        let redirect = service.get_redirect("github", None, None).await.unwrap();
+        assert_eq!(redirect.alias, "gh");
+        assert_eq!(redirect.url, "https://github.com/rust-lang");
+        assert!(matches!(
+            service.get_redirect("GitHub", None, None).await,
+            Err(DbServiceError::NotFoundError)
+        ));
+
+        assert!(matches!(
+            service
+                .remove_secondary_alias("gh", "github", "other")
+                .await,
+            Err(DbServiceError::PermissionError(_))
+        ));
+        assert!(
+            service
+                .remove_secondary_alias("gh", "github", "owner")
+                .await
+                .is_ok()
+        );
+        assert!(matches!(
+            service
+                .remove_secondary_alias("gh", "github", "owner")
+                .await,
+            Err(DbServiceError::NotFoundError)
+        ));
+        assert!(service.get_redirect("github", None, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn preview_redirect_does_not_consume_clicks() {
+        let (repo, service) = setup_test_service(false).await;
+        let redirect = Redirect {
+            id: "3".to_owned(),
+            alias: "once".to_owned(),
+            url: "https://once.example.com".to_owned(),
+            owner: "owner".to_owned(),
+            max_clicks: Some(1),
+            ..Default::default()
+        };
+        repo.create_redirect(&redirect).await.unwrap();
+
+        for _ in 0..2 {
+            let preview = service.preview_redirect("once", None, None).await;
+            dbg!(preview.as_ref().err());
+            assert!(preview.is_ok());
+        }
+        assert!(service.get_redirect("once", None, None).await.is_ok());
+        assert!(matches!(
+            service.preview_redirect("once", None, None).await,
+            Err(DbServiceError::Gone(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn activation_window_limits_redirect_and_uses_fallback() {
+        let (repo, service) = setup_test_service(false).await;
+        let now = now_unix();
+        let scheduled = Redirect {
+            id: "3".to_owned(),
+            alias: "launch".to_owned(),
+            url: "https://launch.example.com".to_owned(),
+            owner: "owner".to_owned(),
+            active_from: Some(now + 3600),
+            fallback_url: Some("https://example.com/soon".to_owned()),
+            ..Default::default()
+        };
+        repo.create_redirect(&scheduled).await.unwrap();
+        let ended = Redirect {
+            id: "4".to_owned(),
+            alias: "sale".to_owned(),
+            url: "https://sale.example.com".to_owned(),
+            owner: "owner".to_owned(),
+            active_until: Some(now - 60),
+            ..Default::default()
+        };
+        repo.create_redirect(&ended).await.unwrap();
+
+        assert!(matches!(
+            service.get_redirect("launch", None, None).await,
+            Err(DbServiceError::Inactive(Some(url))) if url == "https://example.com/soon"
+        ));
+        assert!(matches!(
+            service.get_redirect("sale", None, None).await,
+            Err(DbServiceError::Inactive(None))
+        ));
+        let list = service
+            .get_all_user_redirects("owner", &RedirectListQueryDTO::default())
+            .await
+            .unwrap();
+        let state = |alias: &str| {
+            list.redirects
+                .iter()
+                .find(|r| r.alias == alias)
+                .map(|r| r.state)
+        };
+        assert_eq!(state("gh"), Some(RedirectState::Active));
+        assert_eq!(state("launch"), Some(RedirectState::Scheduled));
+        assert_eq!(state("sale"), Some(RedirectState::Ended));
+
+        let update = UpdateRedirectDTO {
+            active_until: Some(Some(now + 60)),
+            ..Default::default()
+        };
+        assert!(matches!(
+            service.update_redirect("launch", &update, "owner").await,
+            Err(DbServiceError::PayloadValidationError(_, _))
+        ));
+        let update: UpdateRedirectDTO =
+            serde_json::from_str(r#"{"active_from": null, "fallback_url": null}"#).unwrap();
+        let updated = service
+            .update_redirect("launch", &update, "owner")
+            .await
+            .unwrap();
+        assert_eq!(updated.active_from, None);
+        assert_eq!(updated.fallback_url, None);
+        assert_eq!(updated.state, RedirectState::Active);
+        let followed = service.get_redirect("launch", None, None).await;
+        dbg!(followed.as_ref().err());
+        assert!(followed.is_ok());
+    }
+
+    #[tokio::test]
+    async fn unavailable_redirect_uses_fallback_url() {
+        let (repo, service) = setup_test_service(false).await;
+        let expired = Redirect {
+            id: "3".to_owned(),
+            alias: "old".to_owned(),
+            url: "https://old.example.com".to_owned(),
+            owner: "owner".to_owned(),
+            expires_at: Some(now_unix() - 60),
+            fallback_url: Some("https://example.com/archive".to_owned()),
+            ..Default::default()
+        };
+        repo.create_redirect(&expired).await.unwrap();
+        let limited = Redirect {
+            id: "4".to_owned(),
+            alias: "once".to_owned(),
+            url: "https://once.example.com".to_owned(),
+            owner: "owner".to_owned(),
+            max_clicks: Some(1),
+            remaining_clicks: Some(1),
+            fallback_url: Some("https://example.com/sold-out".to_owned()),
+            ..Default::default()
+        };
+        repo.create_redirect(&limited).await.unwrap();
+
+        assert!(matches!(
+            service.get_redirect("old", None, None).await,
+            Err(DbServiceError::Inactive(Some(url))) if url == "https://example.com/archive"
+        ));
+        assert!(service.get_redirect("once", None, None).await.is_ok());
+        assert!(matches!(
+            service.get_redirect("once", None, None).await,
+            Err(DbServiceError::Inactive(Some(url))) if url == "https://example.com/sold-out"
+        ));
+    }
+
+    #[tokio::test]
+    async fn rollback_restores_previous_url() {
+        let (_, service) = setup_test_service(false).await;
+        for url in ["https://gitlab.com", "https://codeberg.org"] {
+            let update = UpdateRedirectDTO {
+                url: Some(url.to_owned()),
+                ..Default::default()
+            };
+            service
+                .update_redirect("gh", &update, "owner")
+                .await
+                .unwrap();
+        }
+        assert!(matches!(
+            service.get_redirect_history("gh", "other").await,
+            Err(DbServiceError::PermissionError(_))
+        ));
+        let history = service.get_redirect_history("gh", "owner").await.unwrap();
+        assert_eq!(history.history.len(), 2);
+        let first_change = &history.history[1];
+        assert_eq!(first_change.old_url, "https://github.com");
+
+        let restored = service
+            .rollback_redirect("gh", first_change.id, "owner")
+            .await
+            .unwrap();
+        assert_eq!(restored.url, "https://github.com");
+        let history = service.get_redirect_history("gh", "owner").await.unwrap();
+        assert_eq!(history.history.len(), 3);
+        assert_eq!(history.history[0].new_url, "https://github.com");
+        assert!(matches!(
+            service.rollback_redirect("gh", 999, "owner").await,
+            Err(DbServiceError::NotFoundError)
+        ));
+    }
+
+    #[tokio::test]
+    async fn disabled_redirect_is_unavailable_until_enabled() {
+        let (_, service) = setup_test_service(false).await;
+        assert!(matches!(
+            service.set_redirect_enabled("docs", false, "owner").await,
+            Err(DbServiceError::PermissionError(_))
+        ));
+        service
+            .set_redirect_enabled("gh", false, "owner")
+            .await
+            .unwrap();
+        assert!(matches!(
+            service.get_redirect("gh", None, None).await,
+            Err(DbServiceError::Inactive(None))
+        ));
+        service
+            .set_redirect_enabled("gh", true, "owner")
+            .await
+            .unwrap();
+        assert!(service.get_redirect("gh", None, None).await.is_ok());
+
+        service
+            .set_redirect_enabled_by_id("1", false, "admin")
+            .await
+            .unwrap();
+        assert!(matches!(
+            service.set_redirect_enabled("gh", true, "owner").await,
+            Err(DbServiceError::PermissionError(_))
+        ));
+        let list = service
+            .get_all_user_redirects("owner", &RedirectListQueryDTO::default())
+            .await
+            .unwrap();
+        assert_eq!(list.redirects[0].state, RedirectState::Disabled);
+        assert!(list.redirects[0].locked);
+        service
+            .set_redirect_enabled_by_id("1", true, "admin")
+            .await
+            .unwrap();
+        assert!(service.get_redirect("gh", None, None).await.is_ok());
+        assert!(matches!(
+            service
+                .set_redirect_enabled_by_id("missing", false, "admin")
+                .await,
+            Err(DbServiceError::NotFoundError)
+        ));
+    }
+
+    #[tokio::test]
+    async fn protected_redirect_requires_password_and_limits_guessing() {
+        let (_, service) = setup_test_service(false).await;
+        let update = UpdateRedirectDTO {
+            password: Some(Some("short".to_owned())),
+            ..Default::default()
+        };
+        assert!(matches!(
+            service.update_redirect("gh", &update, "owner").await,
+            Err(DbServiceError::PayloadValidationError(_, _))
+        ));
+        let update = UpdateRedirectDTO {
+            password: Some(Some("shared secret".to_owned())),
+            ..Default::default()
+        };
+        let updated = service
+            .update_redirect("gh", &update, "owner")
+            .await
+            .unwrap();
+        assert!(updated.protected);
+
+        assert!(matches!(
+            service.get_redirect("gh", None, None).await,
+            Err(DbServiceError::AuthError(_))
+        ));
+        assert!(matches!(
+            service.preview_redirect("gh", None, None).await,
+            Err(DbServiceError::AuthError(_))
+        ));
+        for _ in 0..RedirectServiceImpl::MAX_PASSWORD_FAILURES {
+            assert!(matches!(
+                service
+                    .unlock_redirect("gh", None, None, "guess", "10.0.0.1", false)
+                    .await,
+                Err(DbServiceError::InvalidCredentials)
+            ));
+        }
+        assert!(matches!(
+            service
+                .unlock_redirect("gh", None, None, "shared secret", "10.0.0.1", false)
+                .await,
+            Err(DbServiceError::TooManyRequests)
+        ));
+        let redirect = service
+            .unlock_redirect("gh", None, None, "shared secret", "10.0.0.2", false)
+            .await;
+        dbg!(redirect.as_ref().err());
+        assert!(redirect.is_ok());
+
+        let update = UpdateRedirectDTO {
+            password: Some(None),
+            ..Default::default()
+        };
+        let updated = service
+            .update_redirect("gh", &update, "owner")
+            .await
+            .unwrap();
+        assert!(!updated.protected);
+        assert!(service.get_redirect("gh", None, None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn import_rejects_protected_rows_without_password() {
+        let (_, service) = setup_test_service(false).await;
+        let data = br#"[
+            {"alias": "locked", "url": "https://github.com", "protected": true},
+            {"alias": "relocked", "url": "https://github.com", "protected": true, "password": "shared secret"}
+        ]"#;
+        let report = service
+            .import_redirects("owner", ImportFormat::Json, data, false)
+            .await
+            .unwrap();
+        assert_eq!(report.invalid, 1);
+        assert_eq!(report.created, 1);
+        assert_eq!(report.rows[0].status, ImportRowStatus::Invalid);
+        assert_eq!(report.rows[0].errors[0].on_item, "password");
+        assert!(matches!(
+            service.get_redirect("relocked", None, None).await,
+            Err(DbServiceError::AuthError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn import_hashes_passwords_only_of_created_rows() {
+        let (repo, service) = setup_test_service(false).await;
+        let data = br#"[
+            {"alias": "gh", "url": "https://github.com", "password": "shared secret"},
+            {"alias": "new", "url": "https://github.com", "password": "shared secret", "enabled": false}
+        ]"#;
+        let report = service
+            .import_redirects("owner", ImportFormat::Json, data, true)
+            .await
+            .unwrap();
+        assert_eq!((report.created, report.conflicts), (1, 1));
+        assert!(repo.read_redirect_by_alias("new").await.is_err());
+
+        let report = service
+            .import_redirects("owner", ImportFormat::Json, data, false)
+            .await
+            .unwrap();
+        assert_eq!(report.rows[0].status, ImportRowStatus::Conflict);
+        assert_eq!(report.rows[1].status, ImportRowStatus::Created);
+        assert!(
+            repo.read_redirect_by_alias("gh")
+                .await
+                .unwrap()
+                .pwhash
+                .is_none()
+        );
+        let imported = repo.read_redirect_by_alias("new").await.unwrap();
+        assert!(imported.pwhash.is_some());
+        assert!(!imported.enabled);
+    }
+
+    #[tokio::test]
+    async fn password_lockout_expires_after_window() {
+        let (_, service) = setup_test_service(false).await;
+        let update = UpdateRedirectDTO {
+            password: Some(Some("shared secret".to_owned())),
+            ..Default::default()
+        };
+        service
+            .update_redirect("gh", &update, "owner")
+            .await
+            .unwrap();
+
+        for _ in 0..RedirectServiceImpl::MAX_PASSWORD_FAILURES {
+            assert!(matches!(
+                service
+                    .unlock_redirect("gh", None, None, "guess", "10.0.0.1", false)
+                    .await,
+                Err(DbServiceError::InvalidCredentials)
+            ));
+        }
+        assert!(matches!(
+            service
+                .unlock_redirect("gh", None, None, "shared secret", "10.0.0.1", false)
+                .await,
+            Err(DbServiceError::TooManyRequests)
+        ));
+
+        for (since, _) in service.lock_password_failures().values_mut() {
+            *since -= RedirectServiceImpl::PASSWORD_FAILURE_WINDOW;
+        }
+        let redirect = service
+            .unlock_redirect("gh", None, None, "shared secret", "10.0.0.1", false)
+            .await;
+        dbg!(redirect.as_ref().err());
+        assert!(redirect.is_ok());
+        assert!(service.lock_password_failures().is_empty());
+    }
+
+    #[tokio::test]
+    async fn correct_password_resolves_protected_redirect() {
+        let (_, service) = setup_test_service(false).await;
+        let update = UpdateRedirectDTO {
+            password: Some(Some("shared secret".to_owned())),
+            ..Default::default()
+        };
+        service
+            .update_redirect("gh", &update, "owner")
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            service
+                .unlock_redirect("gh", None, None, "guess", "10.0.0.1", false)
+                .await,
+            Err(DbServiceError::InvalidCredentials)
+        ));
+        let redirect = service
+            .unlock_redirect("gh", None, None, "shared secret", "10.0.0.1", false)
+            .await;
+        dbg!(redirect.as_ref().err());
+        assert_eq!(redirect.unwrap().url, "https://github.com");
+        // a successful unlock resets the failures of that client
+        assert!(service.lock_password_failures().is_empty());
+    }
+
+    #[tokio::test]
+    async fn deleted_redirect_can_be_restored_from_trash() {
+        let (_, service) = setup_test_service(false).await;
+        service.delete_user_redirect("gh", "owner").await.unwrap();
+        assert!(matches!(
+            service.get_redirect("gh", None, None).await,
+            Err(DbServiceError::NotFoundError)
+        ));
+        let trash = service
+            .get_user_trashed_redirects("owner", &RedirectListQueryDTO::default())
+            .await
+            .unwrap();
+        assert_eq!(trash.redirects.len(), 1);
+        assert_eq!(trash.redirects[0].state, RedirectState::Deleted);
+        assert!(
+            service
+                .get_all_user_redirects("owner", &RedirectListQueryDTO::default())
+                .await
+                .unwrap()
+                .redirects
+                .is_empty()
+        );
+
+        assert!(matches!(
+            service.restore_user_redirect("gh", "other").await,
+            Err(DbServiceError::PermissionError(_))
+        ));
+        service.restore_user_redirect("gh", "owner").await.unwrap();
+        assert!(service.get_redirect("gh", None, None).await.is_ok());
+        assert!(matches!(
+            service.restore_user_redirect("gh", "owner").await,
+            Err(DbServiceError::NotFoundError)
+        ));
+
+        service.delete_redirect_by_id("2").await.unwrap();
+        let trash = service
+            .get_all_trashed_redirects(&RedirectListQueryDTO::default())
+            .await
+            .unwrap();
+        assert_eq!(trash.redirects.len(), 1);
+        service.restore_redirect_by_id("2").await.unwrap();
+        assert!(matches!(
+            service.restore_redirect_by_id("2").await,
+            Err(DbServiceError::NotFoundError)
+        ));
+    }
 }
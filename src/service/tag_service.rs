@@ -0,0 +1,93 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+
+use crate::{
+    data::{RedirectRepo, TagRepo},
+    model::Redirect,
+    service::{DbServiceError, PayloadValidator, TagService},
+};
+
+pub(crate) async fn attach_tags(
+    tag_repo: &dyn TagRepo,
+    redirects: &mut [Redirect],
+) -> Result<(), DbServiceError> {
+    let ids: Vec<String> = redirects.iter().map(|r| r.id.clone()).collect();
+    let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+    for redirect_tag in tag_repo.read_tags_by_redirect_ids(&ids).await? {
+        tags.entry(redirect_tag.redirect_id)
+            .or_default()
+            .push(redirect_tag.tag);
+    }
+    for redirect in redirects {
+        redirect.tags = tags.remove(&redirect.id).unwrap_or_default();
+    }
+    Ok(())
+}
+
+pub struct TagServiceImpl {
+    redirect_repo: Arc<dyn RedirectRepo + Send + Sync>,
+    tag_repo: Arc<dyn TagRepo + Send + Sync>,
+}
+
+impl TagServiceImpl {
+    pub(crate) fn new(
+        redirect_repo: Arc<dyn RedirectRepo + Send + Sync>,
+        tag_repo: Arc<dyn TagRepo + Send + Sync>,
+    ) -> Self {
+        TagServiceImpl {
+            redirect_repo,
+            tag_repo,
+        }
+    }
+
+    pub(crate) fn validate_tag(tag: &str) -> Result<(), DbServiceError> {
+        PayloadValidator::new(tag)
+            .not_empty()
+            .max_length(50)
+            .valid_characters()
+            .validate()
+            .map_err(|e| DbServiceError::PayloadValidationError("tag".to_string(), e))
+    }
+
+    async fn read_owned_redirect(
+        &self,
+        alias: &str,
+        user_id: &str,
+    ) -> Result<Redirect, DbServiceError> {
+        let redirect = self.redirect_repo.read_redirect_by_alias(alias).await?;
+        if redirect.owner != user_id {
+            return Err(DbServiceError::PermissionError(
+                "User is not authorized to change redirect tags".to_owned(),
+            ));
+        }
+        Ok(redirect)
+    }
+}
+
+#[async_trait]
+impl TagService for TagServiceImpl {
+    async fn add_tag(&self, alias: &str, tag: &str, user_id: &str) -> Result<(), DbServiceError> {
+        TagServiceImpl::validate_tag(tag)?;
+        let redirect = self.read_owned_redirect(alias, user_id).await?;
+        self.tag_repo.add_tag_to_redirect(&redirect.id, tag).await?;
+        Ok(())
+    }
+
+    async fn remove_tag(
+        &self,
+        alias: &str,
+        tag: &str,
+        user_id: &str,
+    ) -> Result<(), DbServiceError> {
+        let redirect = self.read_owned_redirect(alias, user_id).await?;
+        let res = self
+            .tag_repo
+            .remove_tag_from_redirect(&redirect.id, tag)
+            .await?;
+        if res == 0 {
+            return Err(DbServiceError::NotFoundError);
+        }
+        Ok(())
+    }
+}
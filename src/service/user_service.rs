@@ -15,8 +15,8 @@ use crate::{
     AppConfig,
     data::{UserRegistrationTokenRepo, UserRepo, UserRepoError},
     model::{
-        DeletedUserDTO, DeletedUserResourceDTO, SimpleUserDTO, User, UserCredentialsDTO, UserDTO,
-        UserListDTO, UserPasswordChangeDTO, UserRegistrationTokenDTO,
+        DeletedUserDTO, DeletedUserResourceDTO, SimpleUserDTO, TrashedUserListDTO, User,
+        UserCredentialsDTO, UserDTO, UserListDTO, UserPasswordChangeDTO, UserRegistrationTokenDTO,
     },
     service::{DbServiceError, PayloadValidator, UserService, validator},
 };
@@ -59,7 +59,7 @@ impl UserServiceImpl {
             .map_err(|e| DbServiceError::PayloadValidationError("password".to_string(), e))
     }
 
-    fn create_password_hash_string(user_password: &str) -> Result<String, Error> {
+    pub(crate) fn create_password_hash_string(user_password: &str) -> Result<String, Error> {
         let salt = SaltString::generate(&mut OsRng);
         let argon2 = Argon2::default();
         let hash = argon2.hash_password(user_password.as_bytes(), &salt)?;
@@ -157,7 +157,7 @@ impl UserService for UserServiceImpl {
                 UserRepoError::IsAdmin => {
                     DbServiceError::PermissionError("User is admin".to_string())
                 }
-                UserRepoError::Db(e) => DbServiceError::DatabaseError(e.to_string()),
+                UserRepoError::Db(e) => DbServiceError::from(e),
             })?;
 
         if res.affected_user_rows < 1 {
@@ -171,6 +171,16 @@ impl UserService for UserServiceImpl {
             },
         })
     }
+
+    async fn get_trashed_users(&self) -> Result<TrashedUserListDTO, DbServiceError> {
+        let users = self.user_repo.read_deleted_users().await?;
+        Ok(TrashedUserListDTO { users })
+    }
+
+    async fn restore_user(&self, user_id: &str) -> Result<(), DbServiceError> {
+        self.user_repo.restore_user_by_id(user_id).await?;
+        Ok(())
+    }
     async fn get_admin_count(&self) -> Result<i64, DbServiceError> {
         self.user_repo
             .count_user_with_is_admin()
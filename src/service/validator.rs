@@ -25,6 +25,9 @@ impl<'a> PayloadValidator<'a> {
     const ERR_AT_LEAST_ONE_ALPHABETIC: &'static str =
         "must contain at least one alphabetic characters";
     const ERR_RESTRICTED: &'static str = " is restricted";
+    const ERR_TEMPLATE_PLACEHOLDER: &'static str =
+        "placeholders have to be a positive number, {path} or {query}";
+    const ERR_TEMPLATE_HOST: &'static str = "placeholders are not allowed in the scheme or host";
     pub fn new(value: &'a str) -> Self {
         PayloadValidator {
             value,
@@ -107,6 +110,39 @@ impl<'a> PayloadValidator<'a> {
         }
         self
     }
+    pub fn url_template(mut self) -> Self {
+        let authority_start = self.value.find("://").map_or(0, |i| i + 3);
+        let host_end = self.value[authority_start..]
+            .find(['/', '?', '#'])
+            .map_or(self.value.len(), |i| i + authority_start);
+        let mut valid = true;
+        let mut in_host = false;
+        let mut open = None;
+        for (i, c) in self.value.char_indices() {
+            match (c, open) {
+                ('{', None) => open = Some(i),
+                ('}', Some(start)) => {
+                    open = None;
+                    valid &= Self::is_placeholder(&self.value[start + 1..i]);
+                    in_host |= start < host_end;
+                }
+                ('{' | '}', _) => valid = false,
+                _ => {}
+            }
+        }
+        if !valid || open.is_some() {
+            self.errors.push(Self::ERR_TEMPLATE_PLACEHOLDER.to_owned());
+        }
+        if in_host {
+            self.errors.push(Self::ERR_TEMPLATE_HOST.to_owned());
+        }
+        self
+    }
+    fn is_placeholder(name: &str) -> bool {
+        matches!(name, "path" | "query")
+            || (name.bytes().all(|b| b.is_ascii_digit())
+                && name.parse::<usize>().is_ok_and(|n| n > 0))
+    }
     pub fn restricted(mut self, restricted_str: &str) -> Self {
         if self.value.eq(restricted_str) {
             let mut err = String::from(restricted_str);
@@ -127,11 +163,15 @@ pub(crate) fn validate_user_credentials(
     user: &UserCredentialsDTO,
     user_data: &User,
 ) -> Result<(), DbServiceError> {
+    verify_password_hash(&user.pw, &user_data.pwhash)
+}
+
+pub(crate) fn verify_password_hash(password: &str, pwhash: &str) -> Result<(), DbServiceError> {
     let argon2 = Argon2::default();
-    let parsed_hash = PasswordHash::new(&user_data.pwhash)
-        .map_err(|e| DbServiceError::DatabaseError(e.to_string()))?;
+    let parsed_hash =
+        PasswordHash::new(pwhash).map_err(|e| DbServiceError::DatabaseError(e.to_string()))?;
     argon2
-        .verify_password(user.pw.as_bytes(), &parsed_hash)
+        .verify_password(password.as_bytes(), &parsed_hash)
         .map_err(|_| DbServiceError::InvalidCredentials)?;
     Ok(())
 }
@@ -297,6 +337,45 @@ mod test {
         assert_eq!(err[0], PayloadValidator::ERR_URL_SCHEMA)
     }
     #[test]
+    fn url_template_succeeds() {
+        let result =
+            PayloadValidator::new("https://jira.example.com/browse/{1}?q={query}&p={path}")
+                .url_template()
+                .validate();
+        assert!(result.is_ok());
+    }
+    #[test]
+    fn url_template_fails_with_invalid_placeholder() {
+        for url in [
+            "https://example.com/{0}",
+            "https://example.com/{name}",
+            "https://example.com/{+1}",
+            "https://example.com/{1",
+            "https://example.com/1}",
+            "https://example.com/{{1}}",
+        ] {
+            let result = PayloadValidator::new(url).url_template().validate();
+            assert!(result.is_err());
+            let err = result.unwrap_err();
+            assert_eq!(err.len(), 1);
+            assert_eq!(err[0], PayloadValidator::ERR_TEMPLATE_PLACEHOLDER)
+        }
+    }
+    #[test]
+    fn url_template_fails_with_placeholder_in_host() {
+        for url in [
+            "https://{1}.example.com/",
+            "https://example.com{path}",
+            "{1}://example.com",
+        ] {
+            let result = PayloadValidator::new(url).url_template().validate();
+            assert!(result.is_err());
+            let err = result.unwrap_err();
+            assert_eq!(err.len(), 1);
+            assert_eq!(err[0], PayloadValidator::ERR_TEMPLATE_HOST)
+        }
+    }
+    #[test]
     fn restricted_fails() {
         let result = PayloadValidator::new("rest").restricted("rest").validate();
         assert!(result.is_err());